@@ -0,0 +1,10 @@
+use randpass::{create_password, spell_phonetically, PasswordCriteria, SymbolNames};
+
+fn main() {
+    let password_length = 12;
+    let criteria = PasswordCriteria::AllPrintableChars;
+    let password = create_password(password_length, &criteria, None).unwrap();
+
+    println!("{}", password);
+    println!("{}", spell_phonetically(&password, &SymbolNames::default()));
+}
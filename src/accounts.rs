@@ -0,0 +1,165 @@
+use randpass::{Error, PasswordCriteria};
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+/// Prefixes a cell that a spreadsheet would evaluate as a formula with a
+/// single quote, which makes the spreadsheet treat it as text.
+fn escape_formula(cell: &str) -> Cow<'_, str> {
+    if cell.starts_with(['=', '+', '-', '@']) {
+        Cow::Owned(format!("'{}", cell))
+    } else {
+        Cow::Borrowed(cell)
+    }
+}
+
+/// Reads accounts from a CSV with a `username` column and optional
+/// `length` and `preset` columns, and writes one `username,password` row
+/// per account.
+///
+/// Cells that start with `=`, `+`, `-`, or `@` are prefixed with a single
+/// quote so that spreadsheets do not evaluate them as formulas.
+///
+/// Rows are processed one at a time, so the input can be arbitrarily large.
+/// `generate` is called with the row's length and criteria overrides.
+pub(crate) fn generate_account_credentials<R, W, F>(
+    reader: R,
+    writer: W,
+    mut generate: F,
+) -> Result<(), Error>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(Option<usize>, Option<PasswordCriteria<'static>>) -> Result<String, Error>,
+{
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let mut writer = csv::Writer::from_writer(writer);
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let username_column = column("username")
+        .ok_or_else(|| Error::InvalidCsv("missing 'username' column".to_string()))?;
+    let length_column = column("length");
+    let preset_column = column("preset");
+
+    writer
+        .write_record(["username", "password"])
+        .map_err(csv_error)?;
+
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let line = record.position().map_or(0, |p| p.line());
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|c| record.get(c))
+                .filter(|value| !value.is_empty())
+        };
+        let username = field(Some(username_column))
+            .ok_or_else(|| Error::InvalidCsv(format!("missing username on line {}", line)))?;
+        let password_length = field(length_column)
+            .map(|l| {
+                l.parse::<usize>().map_err(|_| {
+                    Error::InvalidCsv(format!("invalid length '{}' on line {}", l, line))
+                })
+            })
+            .transpose()?;
+        let criteria = field(preset_column)
+            .map(|p| p.parse::<PasswordCriteria>())
+            .transpose()?;
+        let password = generate(password_length, criteria)?;
+
+        writer
+            .write_record([
+                escape_formula(username).as_ref(),
+                escape_formula(&password).as_ref(),
+            ])
+            .map_err(csv_error)?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn csv_error(e: csv::Error) -> Error {
+    Error::InvalidCsv(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_account_credentials() {
+        let input = "username,length,preset\nalice,,\nbob,8,digits\n\"carol, jr\",4,\n";
+        let mut output = Vec::new();
+
+        generate_account_credentials(
+            input.as_bytes(),
+            &mut output,
+            |password_length, criteria| {
+                Ok(format!(
+                    "{}-{}",
+                    password_length.unwrap_or(20),
+                    match criteria {
+                        Some(PasswordCriteria::DigitsOnly) => "digits",
+                        _ => "default",
+                    }
+                ))
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "username,password\n\
+             alice,20-default\n\
+             bob,8-digits\n\
+             \"carol, jr\",4-default\n"
+        );
+    }
+
+    #[test]
+    fn test_escape_formulas() {
+        let input = "username\n=HYPERLINK(\"x\")\n@sum\n+1\nalice\n";
+        let mut passwords = ["-abc", "=1+1", "a=b", "@x"].into_iter();
+        let mut output = Vec::new();
+
+        generate_account_credentials(input.as_bytes(), &mut output, |_, _| {
+            Ok(passwords.next().unwrap().to_string())
+        })
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "username,password\n\
+             \"'=HYPERLINK(\"\"x\"\")\",'-abc\n\
+             '@sum,'=1+1\n\
+             '+1,a=b\n\
+             alice,'@x\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_account_credentials_with_invalid_input() {
+        let generate = |_: Option<usize>, _: Option<PasswordCriteria>| Ok("x".to_string());
+
+        assert!(
+            generate_account_credentials("name\nalice\n".as_bytes(), Vec::new(), generate).is_err()
+        );
+
+        assert!(generate_account_credentials(
+            "username,length\nalice,ten\n".as_bytes(),
+            Vec::new(),
+            generate
+        )
+        .is_err());
+
+        assert!(generate_account_credentials(
+            "username,preset\nalice,unknown\n".as_bytes(),
+            Vec::new(),
+            generate
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use randpass::{BrokenRule, Constraints, Error, PasswordCriteria};
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::util::create_charset;
+
+/// Estimates the entropy of an existing password, assuming that each
+/// character was drawn at random from the character classes it uses.
+///
+/// Uppercase letters, lowercase letters, digits, and ASCII symbols
+/// (including space) each add their full class to the pool; any other
+/// character only adds itself. Passwords chosen by people have far less
+/// entropy, so this is an upper bound.
+pub(crate) fn estimate_entropy(password: &str) -> f64 {
+    let uses = |is_member: fn(&char) -> bool| password.chars().any(|c| is_member(&c));
+    let mut pool = 0;
+
+    if uses(char::is_ascii_uppercase) {
+        pool += 26;
+    }
+    if uses(char::is_ascii_lowercase) {
+        pool += 26;
+    }
+    if uses(char::is_ascii_digit) {
+        pool += 10;
+    }
+    if uses(|c| *c == ' ' || c.is_ascii_punctuation()) {
+        pool += 33;
+    }
+
+    pool += password
+        .chars()
+        .filter(|c| !(c.is_ascii_graphic() || *c == ' '))
+        .collect::<HashSet<char>>()
+        .len();
+
+    if pool < 2 {
+        return 0.0;
+    }
+
+    password.chars().count() as f64 * (pool as f64).log2()
+}
+
+/// A way in which a password breaks a policy.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Violation {
+    /// The password is shorter than the minimum length.
+    TooShort(usize),
+
+    /// The password contains characters outside the charset.
+    DisallowedChars(Vec<char>),
+
+    /// The password lacks some of the required extra characters.
+    MissingChars(Vec<char>),
+
+    /// The password breaks one of the constraints.
+    BrokenRule(BrokenRule),
+}
+
+fn quote_chars(chars: &[char]) -> String {
+    chars
+        .iter()
+        .map(|c| format!("'{}'", c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::TooShort(min_length) => {
+                write!(f, "shorter than {} characters", min_length)
+            }
+            Violation::DisallowedChars(chars) => {
+                write!(f, "contains disallowed {}", quote_chars(chars))
+            }
+            Violation::MissingChars(chars) => write!(f, "lacks required {}", quote_chars(chars)),
+            Violation::BrokenRule(BrokenRule::Repeat) => write!(f, "repeats a character"),
+            Violation::BrokenRule(BrokenRule::Sequence) => {
+                write!(f, "contains an ascending sequence")
+            }
+            Violation::BrokenRule(BrokenRule::Run(max_run)) => write!(
+                f,
+                "has more than {} consecutive characters of one class",
+                max_run
+            ),
+            Violation::BrokenRule(BrokenRule::First(class)) => {
+                write!(f, "does not start with a character of class '{}'", class)
+            }
+            Violation::BrokenRule(BrokenRule::Last(class)) => {
+                write!(f, "does not end with a character of class '{}'", class)
+            }
+            Violation::BrokenRule(BrokenRule::Forbidden(position)) => {
+                write!(f, "has a forbidden character at position {}", position)
+            }
+            Violation::BrokenRule(BrokenRule::TooFew(required)) => write!(
+                f,
+                "has fewer than {} of {:?}",
+                required.count,
+                String::from_utf8_lossy(&required.chars)
+            ),
+        }
+    }
+}
+
+/// Checks a password against a policy: a minimum length, the charset of
+/// the criteria, the extra characters that must each appear, and the
+/// constraints.
+pub(crate) fn check_policy(
+    password: &str,
+    min_length: usize,
+    criteria: &PasswordCriteria,
+    extra_charset: &[u8],
+    constraints: &Constraints,
+) -> Result<Vec<Violation>, Error> {
+    let required_chars: Vec<u8> = constraints
+        .required
+        .iter()
+        .flat_map(|required| required.chars.iter().copied())
+        .collect();
+    let charset = create_charset(criteria, Some(&[extra_charset, &required_chars].concat()))?;
+    let mut violations = Vec::new();
+
+    if password.chars().count() < min_length {
+        violations.push(Violation::TooShort(min_length));
+    }
+
+    let mut disallowed: Vec<char> = password
+        .chars()
+        .filter(|c| !c.is_ascii() || !charset.contains(&(*c as u8)))
+        .collect();
+
+    disallowed.sort_unstable();
+    disallowed.dedup();
+
+    if !disallowed.is_empty() {
+        violations.push(Violation::DisallowedChars(disallowed));
+    }
+
+    let mut remaining = password.as_bytes().to_vec();
+    let mut missing = Vec::new();
+
+    for c in extra_charset {
+        match remaining.iter().position(|r| r == c) {
+            Some(i) => {
+                remaining.swap_remove(i);
+            }
+            None => missing.push(*c as char),
+        }
+    }
+
+    if !missing.is_empty() {
+        violations.push(Violation::MissingChars(missing));
+    }
+
+    violations.extend(
+        constraints
+            .broken_rules(password.as_bytes())
+            .into_iter()
+            .map(Violation::BrokenRule),
+    );
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_entropy() {
+        assert_eq!(estimate_entropy(""), 0.0);
+        assert_eq!(estimate_entropy("aaaa"), 4.0 * 26f64.log2());
+        assert_eq!(estimate_entropy("aB3!"), 4.0 * 95f64.log2());
+        assert_eq!(estimate_entropy("1234é"), 5.0 * 11f64.log2());
+    }
+
+    #[test]
+    fn test_check_policy() {
+        let criteria = PasswordCriteria::Alphanumeric;
+
+        assert_eq!(
+            check_policy("abc!def!", 8, &criteria, b"!!", &Constraints::default()).unwrap(),
+            vec![]
+        );
+
+        assert_eq!(
+            check_policy("ab!é", 8, &criteria, b"!!@", &Constraints::default()).unwrap(),
+            vec![
+                Violation::TooShort(8),
+                Violation::DisallowedChars(vec!['é']),
+                Violation::MissingChars(vec!['!', '@']),
+            ]
+        );
+
+        let constraints = Constraints {
+            no_repeat: true,
+            max_run: Some(4),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check_policy("abcdeff1", 8, &criteria, b"", &constraints).unwrap(),
+            vec![
+                Violation::BrokenRule(BrokenRule::Repeat),
+                Violation::BrokenRule(BrokenRule::Run(4)),
+            ]
+        );
+
+        let constraints = Constraints {
+            required: vec!["2:!@#".parse().unwrap()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check_policy("abcd#ef@", 8, &criteria, b"", &constraints).unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            check_policy("abcdef#1", 8, &criteria, b"", &constraints)
+                .unwrap()
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>(),
+            vec!["has fewer than 2 of \"!@#\""]
+        );
+
+        assert_eq!(
+            Violation::DisallowedChars(vec!['~', 'é']).to_string(),
+            "contains disallowed '~', 'é'"
+        );
+    }
+}
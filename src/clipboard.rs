@@ -0,0 +1,183 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crossterm::event::{self, Event, KeyEventKind};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, IsTerminal, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for the terminal to answer a clipboard query.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Builds the OSC 52 sequence that sets the system clipboard to `payload`,
+/// which is either base64-encoded text or `?` to query the clipboard.
+///
+/// Inside tmux, the sequence is wrapped in a DCS passthrough so that it
+/// reaches the outer terminal.
+fn osc52_sequence(payload: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", payload);
+
+    if in_tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+fn open_tty() -> io::Result<File> {
+    OpenOptions::new().read(true).write(true).open("/dev/tty")
+}
+
+fn write_osc52(payload: &str) -> io::Result<()> {
+    let sequence = osc52_sequence(payload, env::var_os("TMUX").is_some());
+
+    match open_tty() {
+        Ok(mut tty) => tty.write_all(sequence.as_bytes()),
+        Err(_) => io::stderr().write_all(sequence.as_bytes()),
+    }
+}
+
+/// Copies `text` to the clipboard of the controlling terminal.
+///
+/// This works over SSH and without a display server, as long as the
+/// terminal emulator supports OSC 52.
+pub(crate) fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    write_osc52(&STANDARD.encode(text))
+}
+
+/// Extracts the clipboard contents from an OSC 52 reply.
+fn parse_osc52_reply(reply: &[u8]) -> Option<String> {
+    let start = reply.windows(5).position(|w| w == b"\x1b]52;")? + 5;
+    let payload = &reply[start..];
+    let payload = &payload[payload.iter().position(|&c| c == b';')? + 1..];
+    let end = payload
+        .iter()
+        .position(|&c| c == b'\x07' || c == b'\x1b')
+        .unwrap_or(payload.len());
+
+    String::from_utf8(STANDARD.decode(&payload[..end]).ok()?).ok()
+}
+
+/// Waits until `tty` has input to read or `timeout` elapses.
+#[cfg(unix)]
+fn wait_readable(tty: &File, timeout: Duration) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    let mut poll_fd = libc::pollfd {
+        fd: tty.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+
+    // SAFETY: `poll_fd` is a single valid `pollfd` that outlives the call.
+    unsafe { libc::poll(&mut poll_fd, 1, timeout) > 0 }
+}
+
+#[cfg(not(unix))]
+fn wait_readable(_tty: &File, _timeout: Duration) -> bool {
+    false
+}
+
+/// Asks the terminal for the current clipboard contents.
+///
+/// Returns `None` if the terminal does not answer within
+/// [`QUERY_TIMEOUT`], which is common since many terminals disable
+/// clipboard reads.
+fn query_clipboard() -> Option<String> {
+    let mut tty = open_tty().ok()?;
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut reply = Vec::new();
+    let mut byte = [0; 1];
+
+    enable_raw_mode().ok()?;
+
+    let _ = tty.write_all(osc52_sequence("?", env::var_os("TMUX").is_some()).as_bytes());
+
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        if !wait_readable(&tty, remaining) || tty.read_exact(&mut byte).is_err() {
+            break;
+        }
+
+        reply.push(byte[0]);
+
+        if byte[0] == b'\x07' || reply.ends_with(b"\x1b\\") {
+            break;
+        }
+    }
+
+    let _ = disable_raw_mode();
+
+    parse_osc52_reply(&reply)
+}
+
+/// Waits for `timeout` or a key press, then clears the clipboard unless
+/// the terminal reports that it no longer holds `text`.
+///
+/// The check is best-effort: most terminals do not answer clipboard
+/// queries, and then the clipboard is cleared regardless, since leaving a
+/// password in it is worse than clearing something copied since.
+pub(crate) fn clear_clipboard_after(text: &str, timeout: Duration) -> io::Result<()> {
+    let deadline = Instant::now() + timeout;
+
+    if io::stdin().is_terminal() {
+        enable_raw_mode()?;
+
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            if event::poll(remaining)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        break;
+                    }
+                }
+            }
+        }
+
+        disable_raw_mode()?;
+    } else {
+        thread::sleep(timeout);
+    }
+
+    match query_clipboard() {
+        Some(current) if current != text => Ok(()),
+        _ => copy_to_clipboard(""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(
+            osc52_sequence(&STANDARD.encode("hunter2"), false),
+            "\x1b]52;c;aHVudGVyMg==\x07"
+        );
+
+        assert_eq!(
+            osc52_sequence(&STANDARD.encode("hunter2"), true),
+            "\x1bPtmux;\x1b\x1b]52;c;aHVudGVyMg==\x07\x1b\\"
+        );
+
+        assert_eq!(osc52_sequence("?", false), "\x1b]52;c;?\x07");
+    }
+
+    #[test]
+    fn test_parse_osc52_reply() {
+        assert_eq!(
+            parse_osc52_reply(b"\x1b]52;c;aHVudGVyMg==\x07"),
+            Some("hunter2".to_string())
+        );
+
+        assert_eq!(
+            parse_osc52_reply(b"\x1b]52;c;aHVudGVyMg==\x1b\\"),
+            Some("hunter2".to_string())
+        );
+
+        assert_eq!(parse_osc52_reply(b"\x1b]52;c;\x07"), Some("".to_string()));
+
+        assert_eq!(parse_osc52_reply(b"garbage"), None);
+    }
+}
@@ -0,0 +1,453 @@
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use randpass::Error;
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::str::FromStr;
+use toml::{Table, Value};
+
+/// Options that cannot be set in the configuration file.
+const RESERVED_OPTIONS: [&str; 3] = ["help", "version", "profile"];
+
+/// Default settings and named profiles, keyed by long option name.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Config {
+    defaults: Table,
+    profiles: HashMap<String, Table>,
+}
+
+/// Settings from one part of the configuration file.
+#[derive(Clone)]
+pub(crate) struct Layer<'a> {
+    pub(crate) source: String,
+    pub(crate) settings: &'a Table,
+}
+
+impl FromStr for Config {
+    type Err = Error;
+
+    /// Parses a configuration file with top-level defaults and a
+    /// `[profiles.<name>]` table per profile.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut defaults = s
+            .parse::<Table>()
+            .map_err(|e| Error::InvalidConfig(e.message().to_string()))?;
+        let profiles = match defaults.remove("profiles") {
+            Some(Value::Table(profiles)) => profiles
+                .into_iter()
+                .map(|(name, profile)| match profile {
+                    Value::Table(settings) => Ok((name, settings)),
+                    _ => Err(Error::InvalidConfig(format!(
+                        "profile '{}' is not a table",
+                        name
+                    ))),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => {
+                return Err(Error::InvalidConfig(
+                    "'profiles' is not a table".to_string(),
+                ))
+            }
+            None => HashMap::new(),
+        };
+
+        Ok(Config { defaults, profiles })
+    }
+}
+
+impl Config {
+    /// Returns `$XDG_CONFIG_HOME/randpass/config.toml`, falling back to
+    /// `~/.config/randpass/config.toml`.
+    pub(crate) fn path() -> Option<PathBuf> {
+        let config_home = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+
+        Some(config_home.join("randpass").join("config.toml"))
+    }
+
+    /// Loads the configuration file, or an empty configuration if there is
+    /// none.
+    pub(crate) fn load() -> Result<Self, Error> {
+        let Some(path) = Config::path() else {
+            return Ok(Config::default());
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => contents.parse(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the settings of `profile` and the defaults, in order of
+    /// precedence.
+    pub(crate) fn layers(&self, profile: Option<&str>) -> Result<Vec<Layer<'_>>, Error> {
+        let mut layers = Vec::with_capacity(2);
+
+        if let Some(name) = profile {
+            let settings = self
+                .profiles
+                .get(name)
+                .ok_or_else(|| Error::UnknownProfile(name.to_string()))?;
+
+            layers.push(Layer {
+                source: format!("profile '{}'", name),
+                settings,
+            });
+        }
+
+        layers.push(Layer {
+            source: "config".to_string(),
+            settings: &self.defaults,
+        });
+
+        Ok(layers)
+    }
+}
+
+/// Turns a setting into command-line arguments for `arg`.
+fn setting_to_args(arg: &Arg, name: &str, value: &Value) -> Result<Vec<OsString>, Error> {
+    let invalid = || Error::InvalidConfig(format!("invalid value for '{}'", name));
+
+    match (arg.get_action(), value) {
+        (ArgAction::SetTrue, Value::Boolean(true)) => Ok(vec![format!("--{}", name).into()]),
+        (ArgAction::SetTrue, Value::Boolean(false)) => Ok(vec![]),
+        (ArgAction::SetTrue, _) => Err(invalid()),
+        (ArgAction::Append, Value::Array(values)) => values
+            .iter()
+            .map(|v| match v {
+                Value::Array(_) => Err(invalid()),
+                v => setting_to_args(arg, name, v)?.pop().ok_or_else(invalid),
+            })
+            .collect(),
+        (_, Value::String(s)) => Ok(vec![format!("--{}={}", name, s).into()]),
+        (_, Value::Integer(i)) => Ok(vec![format!("--{}={}", name, i).into()]),
+        (_, Value::Float(x)) => Ok(vec![format!("--{}={}", name, x).into()]),
+        _ => Err(invalid()),
+    }
+}
+
+/// Checks whether either argument declares a conflict with the other, or
+/// both belong to a group that accepts only one of its arguments.
+pub(crate) fn conflicts(command: &Command, a: &Arg, b: &Arg) -> bool {
+    command
+        .get_arg_conflicts_with(a)
+        .iter()
+        .any(|c| c.get_id() == b.get_id())
+        || command
+            .get_arg_conflicts_with(b)
+            .iter()
+            .any(|c| c.get_id() == a.get_id())
+        || a.get_id() != b.get_id()
+            && command.get_groups().any(|g| {
+                !g.clone().is_multiple()
+                    && g.get_args().any(|id| id == a.get_id())
+                    && g.get_args().any(|id| id == b.get_id())
+            })
+}
+
+/// Translates the configured settings into command-line arguments to parse
+/// before the given ones.
+///
+/// A setting is skipped if its option was given on the command line, was
+/// set by a layer of higher precedence, such as the environment, or
+/// conflicts with one of those.
+///
+/// Returns the arguments and the source of each option they set, keyed by
+/// argument id.
+pub(crate) fn resolve_settings(
+    command: &Command,
+    matches: &ArgMatches,
+    layers: &[Layer],
+) -> Result<(Vec<OsString>, HashMap<String, String>), Error> {
+    let set = command
+        .get_arguments()
+        .filter(|a| matches.value_source(a.get_id().as_str()) == Some(ValueSource::CommandLine))
+        .collect();
+
+    resolve_layers(command, set, layers)
+}
+
+/// Translates the configured settings into command-line arguments, given
+/// the arguments that are already set.
+fn resolve_layers<'a>(
+    command: &'a Command,
+    mut set: Vec<&'a Arg>,
+    layers: &[Layer],
+) -> Result<(Vec<OsString>, HashMap<String, String>), Error> {
+    let mut args = Vec::new();
+    let mut sources = HashMap::new();
+
+    for layer in layers {
+        for (name, value) in layer.settings {
+            let arg = command
+                .get_arguments()
+                .find(|a| a.get_long() == Some(name) && !RESERVED_OPTIONS.contains(&name.as_str()))
+                .ok_or_else(|| Error::InvalidConfig(format!("unknown setting '{}'", name)))?;
+
+            if set
+                .iter()
+                .any(|s| s.get_id() == arg.get_id() || conflicts(command, s, arg))
+            {
+                continue;
+            }
+
+            args.extend(setting_to_args(arg, name, value)?);
+            set.push(arg);
+            sources.insert(arg.get_id().to_string(), layer.source.clone());
+        }
+    }
+
+    Ok((args, sources))
+}
+
+/// Keeps the configured settings that apply to a command other than
+/// `generate`.
+///
+/// Only the settings of the options that `command` shares with `generate`,
+/// given by their ids, are kept; the others must still be valid options of
+/// `generate`.
+///
+/// Returns the source and the kept settings of each layer.
+pub(crate) fn shared_settings(
+    generate: &Command,
+    command: &Command,
+    layers: &[Layer],
+    shared: &[String],
+) -> Result<Vec<(String, Table)>, Error> {
+    let mut tables = Vec::with_capacity(layers.len());
+
+    for layer in layers {
+        let mut table = Table::new();
+
+        for (name, value) in layer.settings {
+            let arg = generate
+                .get_arguments()
+                .find(|a| a.get_long() == Some(name) && !RESERVED_OPTIONS.contains(&name.as_str()))
+                .ok_or_else(|| Error::InvalidConfig(format!("unknown setting '{}'", name)))?;
+
+            setting_to_args(arg, name, value)?;
+
+            if shared.contains(&arg.get_id().to_string())
+                && command.get_arguments().any(|a| a.get_id() == arg.get_id())
+            {
+                table.insert(name.clone(), value.clone());
+            }
+        }
+
+        tables.push((layer.source.clone(), table));
+    }
+
+    Ok(tables)
+}
+
+fn format_value(arg: &Arg, value: &str) -> String {
+    if matches!(arg.get_action(), ArgAction::SetTrue) || value.parse::<i64>().is_ok() {
+        value.to_string()
+    } else {
+        Value::String(value.to_string()).to_string()
+    }
+}
+
+/// Renders the effective settings as TOML, with the source of each value
+/// in a comment.
+///
+/// The values of the options in `sources` are read from `layered`, the
+/// matches of the resolved settings, and the others from `matches`.
+pub(crate) fn render_settings(
+    command: &Command,
+    matches: &ArgMatches,
+    layered: &ArgMatches,
+    sources: &HashMap<String, String>,
+) -> String {
+    let mut rendered = String::new();
+
+    for arg in command.get_arguments() {
+        let (Some(name), id) = (arg.get_long(), arg.get_id().as_str()) else {
+            continue;
+        };
+
+        if RESERVED_OPTIONS.contains(&name) {
+            continue;
+        }
+
+        let raw = if sources.contains_key(id) {
+            layered.get_raw(id)
+        } else {
+            matches.get_raw(id)
+        };
+        let Some(raw) = raw else {
+            continue;
+        };
+        let values: Vec<String> = raw
+            .map(|v| format_value(arg, &v.to_string_lossy()))
+            .collect();
+        let value = if matches!(arg.get_action(), ArgAction::Append) {
+            format!("[{}]", values.join(", "))
+        } else {
+            values.join(" ")
+        };
+        let source = match sources.get(id) {
+            Some(source) => source.as_str(),
+            None => match matches.value_source(id) {
+                Some(ValueSource::DefaultValue) => "default",
+                _ => "command line",
+            },
+        };
+
+        rendered.push_str(&format!("{} = {}  # {}\n", name, value, source));
+    }
+
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ArgGroup;
+
+    fn command() -> Command {
+        Command::new("test")
+            .arg(
+                Arg::new("length")
+                    .short('l')
+                    .long("length")
+                    .default_value("20"),
+            )
+            .arg(
+                Arg::new("symbols")
+                    .short('s')
+                    .long("symbols")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with("base"),
+            )
+            .arg(Arg::new("base").short('b').long("base"))
+            .arg(
+                Arg::new("digits")
+                    .short('d')
+                    .long("digits")
+                    .action(ArgAction::SetTrue),
+            )
+            .arg(Arg::new("entry").long("entry").action(ArgAction::Append))
+            .group(ArgGroup::new("charset").args(["base", "digits"]))
+    }
+
+    fn resolve(config: &str, profile: Option<&str>, argv: &[&str]) -> Vec<OsString> {
+        let config = config.parse::<Config>().unwrap();
+        let matches = command().get_matches_from(argv);
+
+        resolve_settings(&command(), &matches, &config.layers(profile).unwrap())
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = "length = 24\n[profiles.db]\nsymbols = true\n"
+            .parse::<Config>()
+            .unwrap();
+
+        assert_eq!(config.defaults.get("length"), Some(&Value::Integer(24)));
+        assert_eq!(
+            config.profiles["db"].get("symbols"),
+            Some(&Value::Boolean(true))
+        );
+
+        assert!("length = ".parse::<Config>().is_err());
+        assert!("profiles = 1".parse::<Config>().is_err());
+        assert!("[profiles]\ndb = 1".parse::<Config>().is_err());
+        assert!(matches!(
+            Config::default().layers(Some("db")),
+            Err(Error::UnknownProfile(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_settings() {
+        let config = "length = 24\nentry = ['a', 'b']\n\
+                      [profiles.db]\nlength = 32\nbase = 'abc'\n";
+
+        assert_eq!(
+            resolve(config, None, &["test"]),
+            vec!["--entry=a", "--entry=b", "--length=24"]
+        );
+
+        // The profile overrides the defaults.
+        assert_eq!(
+            resolve(config, Some("db"), &["test"]),
+            vec!["--base=abc", "--length=32", "--entry=a", "--entry=b"]
+        );
+
+        // The command line overrides both, including conflicting settings.
+        assert_eq!(
+            resolve(config, Some("db"), &["test", "-l", "8", "-s"]),
+            vec!["--entry=a", "--entry=b"]
+        );
+
+        // An option also overrides the settings of the other options in its
+        // group.
+        assert_eq!(
+            resolve(config, Some("db"), &["test", "-d"]),
+            vec!["--length=32", "--entry=a", "--entry=b"]
+        );
+
+        // So does any other argument that is already set, such as one read
+        // from an environment variable.
+        let env_command = command();
+        let base = env_command
+            .get_arguments()
+            .find(|a| a.get_id() == "base")
+            .unwrap();
+        let layered = config.parse::<Config>().unwrap();
+
+        assert_eq!(
+            resolve_layers(
+                &env_command,
+                vec![base],
+                &layered.layers(Some("db")).unwrap()
+            )
+            .unwrap()
+            .0,
+            vec!["--length=32", "--entry=a", "--entry=b"]
+        );
+
+        let matches = command().get_matches_from(["test"]);
+        let config = "colour = true".parse::<Config>().unwrap();
+
+        assert!(resolve_settings(&command(), &matches, &config.layers(None).unwrap()).is_err());
+
+        let config = "symbols = 'yes'".parse::<Config>().unwrap();
+
+        assert!(resolve_settings(&command(), &matches, &config.layers(None).unwrap()).is_err());
+
+        for config in ["entry = [[]]", "entry = [['a']]"] {
+            let config = config.parse::<Config>().unwrap();
+
+            assert!(matches!(
+                resolve_settings(&command(), &matches, &config.layers(None).unwrap()),
+                Err(Error::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_render_settings() {
+        let matches = command().get_matches_from(["test", "--entry=a"]);
+        let layered = command().get_matches_from(["test", "-s"]);
+        let sources = HashMap::from([("symbols".to_string(), "profile 'db'".to_string())]);
+
+        assert_eq!(
+            render_settings(&command(), &matches, &layered, &sources),
+            "length = 20  # default\n\
+             symbols = true  # profile 'db'\n\
+             digits = false  # default\n\
+             entry = [\"a\"]  # command line\n"
+        );
+    }
+}
@@ -0,0 +1,1115 @@
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::util::calculate_char_multiplicity_pairs;
+use crate::Error;
+
+/// Names of the character classes, as parsed and displayed.
+const CHAR_CLASS_NAMES: [(&str, CharClass); 6] = [
+    ("upper", CharClass::Uppercase),
+    ("lower", CharClass::Lowercase),
+    ("letter", CharClass::Letter),
+    ("digit", CharClass::Digit),
+    ("alnum", CharClass::Alphanumeric),
+    ("symbol", CharClass::Symbol),
+];
+
+/// A class of characters that a position may be restricted to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharClass {
+    Uppercase,
+    Lowercase,
+    Letter,
+    Digit,
+    Alphanumeric,
+
+    /// Any character other than a letter or digit.
+    Symbol,
+}
+
+impl CharClass {
+    /// Checks whether `c` belongs to the class.
+    pub fn contains(self, c: u8) -> bool {
+        match self {
+            CharClass::Uppercase => c.is_ascii_uppercase(),
+            CharClass::Lowercase => c.is_ascii_lowercase(),
+            CharClass::Letter => c.is_ascii_alphabetic(),
+            CharClass::Digit => c.is_ascii_digit(),
+            CharClass::Alphanumeric => c.is_ascii_alphanumeric(),
+            CharClass::Symbol => !c.is_ascii_alphanumeric(),
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = CHAR_CLASS_NAMES
+            .iter()
+            .find(|(_, class)| class == self)
+            .expect("every class has a name");
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for CharClass {
+    type Err = Error;
+
+    /// Parses a class name: `upper`, `lower`, `letter`, `digit`, `alnum`,
+    /// or `symbol`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CHAR_CLASS_NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+            .map(|&(_, class)| class)
+            .ok_or_else(|| Error::UnknownCharClass(s.to_string()))
+    }
+}
+
+/// A position in a password, as an offset from its start or its end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Position {
+    Start(usize),
+    End(usize),
+}
+
+impl Position {
+    /// Returns the index of the position in a password of
+    /// `password_length` characters, if it has one.
+    pub fn index(self, password_length: usize) -> Option<usize> {
+        match self {
+            Position::Start(offset) => Some(offset).filter(|&i| i < password_length),
+            Position::End(offset) => password_length.checked_sub(offset + 1),
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Position::Start(0) => write!(f, "the first position"),
+            Position::End(0) => write!(f, "the last position"),
+            Position::Start(offset) => write!(f, "position {}", offset + 1),
+            Position::End(offset) => write!(f, "position {} from the end", offset + 1),
+        }
+    }
+}
+
+/// Characters that must not appear at a position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForbiddenChars {
+    pub position: Position,
+    pub chars: Vec<u8>,
+}
+
+impl FromStr for ForbiddenChars {
+    type Err = Error;
+
+    /// Parses a `position:chars` pair, where the position is `first`,
+    /// `last`, a number counted from 1, or a negative number counted from
+    /// the end, such as `last:!?` or `-2:0O`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidForbiddenChars(s.to_string());
+        let (position, chars) = s.split_once(':').ok_or_else(invalid)?;
+        let number = |n: &str| {
+            n.parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(invalid)
+        };
+        let position = match position.trim() {
+            "first" => Position::Start(0),
+            "last" => Position::End(0),
+            n => match n.strip_prefix('-') {
+                Some(n) => Position::End(number(n)? - 1),
+                None => Position::Start(number(n)? - 1),
+            },
+        };
+
+        if chars.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(ForbiddenChars {
+            position,
+            chars: chars.as_bytes().to_vec(),
+        })
+    }
+}
+
+/// A minimum number of characters drawn from a set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequiredChars {
+    pub count: usize,
+    pub chars: Vec<u8>,
+}
+
+impl FromStr for RequiredChars {
+    type Err = Error;
+
+    /// Parses a `count:chars` pair, such as `2:!@#` for at least two of
+    /// `!`, `@`, and `#`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidRequiredChars(s.to_string());
+        let (count, chars) = s.split_once(':').ok_or_else(invalid)?;
+        let count = count.trim().parse::<usize>().map_err(|_| invalid())?;
+
+        if count == 0 || chars.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(RequiredChars {
+            count,
+            chars: chars.as_bytes().to_vec(),
+        })
+    }
+}
+
+/// A value of `--extra`: characters that a password contains each time
+/// they are listed, or a minimum number of characters drawn from a set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExtraChars {
+    Literal(Vec<u8>),
+    Required(RequiredChars),
+}
+
+impl FromStr for ExtraChars {
+    type Err = Error;
+
+    /// Parses a `count:chars` pair as [`RequiredChars`] and anything else
+    /// as literal characters. A leading backslash before a `count:` prefix
+    /// is dropped and keeps the rest literal, such as `\12:30` for `12:30`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_counted = |s: &str| {
+            s.split_once(':').is_some_and(|(count, _)| {
+                !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit())
+            })
+        };
+
+        match s.strip_prefix('\\') {
+            Some(rest) if is_counted(rest) => Ok(ExtraChars::Literal(rest.as_bytes().to_vec())),
+            _ if is_counted(s) => Ok(ExtraChars::Required(s.parse()?)),
+            _ => Ok(ExtraChars::Literal(s.as_bytes().to_vec())),
+        }
+    }
+}
+
+/// Rules on the characters of a password: on adjacent characters, on the
+/// characters allowed at some positions, and on how many characters of
+/// some sets it contains.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Constraints {
+    /// Forbids a character right after the same one, such as `aa`.
+    pub no_repeat: bool,
+
+    /// Forbids three ascending characters of one class, such as `abc` or
+    /// `123`.
+    pub no_sequences: bool,
+
+    /// Maximum number of consecutive characters of one class: uppercase
+    /// letters, lowercase letters, digits, or symbols.
+    pub max_run: Option<usize>,
+
+    /// Class of the first character.
+    pub first: Option<CharClass>,
+
+    /// Class of the last character.
+    pub last: Option<CharClass>,
+
+    /// Characters that must not appear at some positions.
+    pub forbidden: Vec<ForbiddenChars>,
+
+    /// Sets of which the password contains at least some characters, in
+    /// addition to the extra characters.
+    pub required: Vec<RequiredChars>,
+}
+
+/// A rule of [`Constraints`] that a password breaks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BrokenRule {
+    /// A character follows the same one.
+    Repeat,
+
+    /// Three ascending characters of one class follow each other.
+    Sequence,
+
+    /// More characters of one class than the maximum follow each other.
+    Run(usize),
+
+    /// The first character is not of the class.
+    First(CharClass),
+
+    /// The last character is not of the class.
+    Last(CharClass),
+
+    /// A forbidden character is at the position, counted from 1.
+    Forbidden(usize),
+
+    /// Fewer characters of the set than required.
+    TooFew(RequiredChars),
+}
+
+impl Constraints {
+    /// Checks whether no rule is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Constraints::default()
+    }
+
+    /// Returns the rules that `password` breaks.
+    pub fn broken_rules(&self, password: &[u8]) -> Vec<BrokenRule> {
+        let mut broken = Vec::new();
+
+        if self.no_repeat && password.windows(2).any(|w| w[0] == w[1]) {
+            broken.push(BrokenRule::Repeat);
+        }
+
+        if self.no_sequences
+            && password
+                .windows(3)
+                .any(|w| ascends(w[0], w[1]) && ascends(w[1], w[2]))
+        {
+            broken.push(BrokenRule::Sequence);
+        }
+
+        if let Some(max_run) = self.max_run {
+            if password
+                .chunk_by(|&a, &b| char_class(a) == char_class(b))
+                .any(|run| run.len() > max_run)
+            {
+                broken.push(BrokenRule::Run(max_run));
+            }
+        }
+
+        if let (Some(class), Some(&c)) = (self.first, password.first()) {
+            if !class.contains(c) {
+                broken.push(BrokenRule::First(class));
+            }
+        }
+
+        if let (Some(class), Some(&c)) = (self.last, password.last()) {
+            if !class.contains(c) {
+                broken.push(BrokenRule::Last(class));
+            }
+        }
+
+        broken.extend(
+            password
+                .iter()
+                .enumerate()
+                .filter(|&(i, &c)| self.forbids_char(password.len(), i, c))
+                .map(|(i, _)| BrokenRule::Forbidden(i + 1)),
+        );
+
+        broken.extend(
+            self.required
+                .iter()
+                .filter(|required| {
+                    password
+                        .iter()
+                        .filter(|c| required.chars.contains(c))
+                        .count()
+                        < required.count
+                })
+                .cloned()
+                .map(BrokenRule::TooFew),
+        );
+
+        broken
+    }
+
+    /// Checks whether `c` is one of the forbidden characters at `index` in
+    /// a password of `password_length` characters.
+    fn forbids_char(&self, password_length: usize, index: usize, c: u8) -> bool {
+        self.forbidden.iter().any(|forbidden| {
+            forbidden.position.index(password_length) == Some(index) && forbidden.chars.contains(&c)
+        })
+    }
+
+    /// Checks whether `c` may be at `index` in a password of
+    /// `password_length` characters, regardless of its neighbors.
+    fn allows(&self, password_length: usize, index: usize, c: u8) -> bool {
+        let first = index != 0 || self.first.is_none_or(|class| class.contains(c));
+        let last = index + 1 != password_length || self.last.is_none_or(|class| class.contains(c));
+
+        first && last && !self.forbids_char(password_length, index, c)
+    }
+
+    /// Returns the characters of the required sets, which the charset must
+    /// include.
+    pub(crate) fn required_chars(&self) -> Vec<u8> {
+        self.required
+            .iter()
+            .flat_map(|required| required.chars.iter().copied())
+            .collect()
+    }
+
+    /// Returns the sets that a password contains at least some characters
+    /// of: one for each distinct extra character, then the required sets,
+    /// with each set of characters once and its largest count.
+    pub(crate) fn sets(&self, extra_charset: &[u8]) -> Vec<(Vec<u8>, usize)> {
+        let mut sets: Vec<(Vec<u8>, usize)> = calculate_char_multiplicity_pairs(extra_charset)
+            .into_iter()
+            .map(|(c, n)| (vec![c], n))
+            .collect();
+
+        for required in &self.required {
+            let mut chars = required.chars.clone();
+
+            chars.sort_unstable();
+            chars.dedup();
+
+            match sets.iter_mut().find(|(set, _)| *set == chars) {
+                Some((_, count)) => *count = (*count).max(required.count),
+                None => sets.push((chars, required.count)),
+            }
+        }
+
+        sets
+    }
+
+    /// Returns the sets of [`Constraints::sets`] if no rule other than the
+    /// required sets is set and no two of them share a character, so that
+    /// the passwords are counted like those with extra characters.
+    pub(crate) fn disjoint_sets(&self, extra_charset: &[u8]) -> Option<Vec<(Vec<u8>, usize)>> {
+        let only_required = Constraints {
+            required: self.required.clone(),
+            ..Default::default()
+        };
+
+        if *self != only_required {
+            return None;
+        }
+
+        let sets = self.sets(extra_charset);
+
+        are_disjoint(&sets).then_some(sets)
+    }
+
+    /// Calculates the shortest length of a password that contains the
+    /// extra characters and the required sets, with a character counting
+    /// toward every set that contains it.
+    ///
+    /// # Parameters
+    /// - `extra_charset`: The extra characters that the password contains.
+    ///
+    /// # Returns
+    /// The shortest length, or the largest count of a set if the sets
+    /// overlap in too many ways to search, which never rejects a length
+    /// that could be reached.
+    pub fn min_length(&self, extra_charset: &[u8]) -> usize {
+        let sets = self.sets(extra_charset);
+
+        if are_disjoint(&sets) {
+            return sets.iter().map(|&(_, n)| n).sum();
+        }
+
+        let mut strides = Vec::with_capacity(sets.len());
+        let mut states = 1_usize;
+
+        for &(_, n) in &sets {
+            strides.push(states);
+            states = match states.checked_mul(n + 1) {
+                Some(states) if states <= MAX_MIN_LENGTH_STATES => states,
+                _ => return sets.iter().map(|&(_, n)| n).max().unwrap_or(0),
+            };
+        }
+
+        // The sets that each character counts toward.
+        let mut places: Vec<Vec<usize>> = sets
+            .iter()
+            .flat_map(|(chars, _)| chars.iter())
+            .map(|c| (0..sets.len()).filter(|&i| sets[i].0.contains(c)).collect())
+            .collect();
+
+        places.sort_unstable();
+        places.dedup();
+
+        // Breadth-first search from every count missing to none missing.
+        let start: usize = sets.iter().zip(&strides).map(|(&(_, n), s)| n * s).sum();
+        let mut lengths = vec![usize::MAX; states];
+        let mut queue = VecDeque::from([start]);
+
+        lengths[start] = 0;
+
+        while let Some(state) = queue.pop_front() {
+            if state == 0 {
+                return lengths[0];
+            }
+
+            for place in &places {
+                let next = place
+                    .iter()
+                    .filter(|&&i| (state / strides[i]) % (sets[i].1 + 1) > 0)
+                    .fold(state, |next, &i| next - strides[i]);
+
+                if lengths[next] == usize::MAX {
+                    lengths[next] = lengths[state] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        lengths[0]
+    }
+}
+
+fn char_class(c: u8) -> u8 {
+    if c.is_ascii_uppercase() {
+        0
+    } else if c.is_ascii_lowercase() {
+        1
+    } else if c.is_ascii_digit() {
+        2
+    } else {
+        3
+    }
+}
+
+/// Checks whether no two of the `sets` share a character.
+fn are_disjoint(sets: &[(Vec<u8>, usize)]) -> bool {
+    sets.iter().enumerate().all(|(i, (chars, _))| {
+        sets[i + 1..]
+            .iter()
+            .all(|(other, _)| !chars.iter().any(|c| other.contains(c)))
+    })
+}
+
+/// Checks whether `b` follows `a` in an ascending sequence.
+fn ascends(a: u8, b: u8) -> bool {
+    a.checked_add(1) == Some(b) && char_class(a) == char_class(b)
+}
+
+/// The most transitions that a [`Counter`] goes through, the number of
+/// states times the charset size times the password length, which takes
+/// about a second.
+const MAX_COUNTER_STEPS: usize = 1 << 26;
+
+/// The most counts of the required sets still missing that
+/// [`Constraints::min_length`] searches through.
+const MAX_MIN_LENGTH_STATES: usize = 1 << 20;
+
+/// The number of valid passwords of each length from each state, used to
+/// count and to sample them uniformly.
+///
+/// A state is the last character, the length of the run of its class,
+/// whether it ascends from the one before, and how many characters of
+/// each required set are still missing.
+pub(crate) struct Counter<'a> {
+    password_length: usize,
+    charset: Vec<u8>,
+    constraints: &'a Constraints,
+
+    /// Sets of characters and their minimum number of occurrences: one
+    /// for each distinct extra character, then the required sets.
+    required: Vec<(Vec<u8>, usize)>,
+
+    /// For each character of the charset, the place value and the size of
+    /// the digit of each set that contains it in the missing characters.
+    places: Vec<Vec<(usize, usize)>>,
+
+    /// Number of values of the run length.
+    runs: usize,
+
+    /// Number of combinations of missing extra characters.
+    missing: usize,
+
+    /// `counts[k][state]` is the number of ways to append `k` characters,
+    /// divided by `2^scales[k]`.
+    counts: Vec<Vec<f64>>,
+    scales: Vec<f64>,
+}
+
+impl<'a> Counter<'a> {
+    /// Counts the valid passwords of `password_length` characters of
+    /// `charset` with at least the given extra characters.
+    ///
+    /// Fails with [`Error::ConstraintsTooComplex`] rather than going
+    /// through more than [`MAX_COUNTER_STEPS`] transitions.
+    pub(crate) fn new(
+        password_length: usize,
+        charset: &[u8],
+        extra_charset: &[u8],
+        constraints: &'a Constraints,
+    ) -> Result<Self, Error> {
+        let required = constraints.sets(extra_charset);
+        // A run never outgrows the password.
+        let runs = constraints
+            .max_run
+            .map_or(1, |n| n.min(password_length) + 1);
+        let (missing, states) = required
+            .iter()
+            .try_fold(1, |missing: usize, &(_, n)| missing.checked_mul(n + 1))
+            .and_then(|missing| Some((missing, missing.checked_mul(charset.len() * runs * 2)?)))
+            .filter(|&(_, states)| {
+                states
+                    .checked_mul(charset.len() * password_length)
+                    .is_some_and(|steps| steps <= MAX_COUNTER_STEPS)
+            })
+            .ok_or(Error::ConstraintsTooComplex)?;
+        let places = charset
+            .iter()
+            .map(|c| {
+                let mut radix = 1;
+                let mut places = Vec::new();
+
+                for (chars, n) in required.iter().rev() {
+                    if chars.contains(c) {
+                        places.push((radix, *n));
+                    }
+
+                    radix *= n + 1;
+                }
+
+                places
+            })
+            .collect();
+        let mut counter = Counter {
+            password_length,
+            charset: charset.to_vec(),
+            constraints,
+            required,
+            places,
+            runs,
+            missing,
+            counts: Vec::with_capacity(password_length),
+            scales: Vec::with_capacity(password_length),
+        };
+
+        if password_length == 0 {
+            return Ok(counter);
+        }
+
+        counter.counts.push(
+            (0..states)
+                .map(|state| (state % missing == 0) as u8 as f64)
+                .collect(),
+        );
+        counter.scales.push(0.0);
+
+        for k in 1..password_length {
+            let mut layer: Vec<f64> = (0..states)
+                .map(|state| {
+                    counter
+                        .next_states(Some(state), password_length - k)
+                        .map(|(_, next)| counter.counts[k - 1][next])
+                        .sum()
+                })
+                .collect();
+            let most = layer.iter().cloned().fold(0.0, f64::max);
+            let mut scale = counter.scales[k - 1];
+
+            if most > 0.0 {
+                let shift = most.log2().floor();
+
+                layer.iter_mut().for_each(|count| *count /= shift.exp2());
+                scale += shift;
+            }
+
+            counter.counts.push(layer);
+            counter.scales.push(scale);
+        }
+
+        Ok(counter)
+    }
+
+    /// Returns the index of a state.
+    fn state(&self, last: usize, run: usize, ascending: bool, missing: usize) -> usize {
+        ((last * self.runs + run.min(self.runs - 1)) * 2 + ascending as usize) * self.missing
+            + missing
+    }
+
+    /// Returns the missing characters before any character.
+    fn initial_missing(&self) -> usize {
+        self.required
+            .iter()
+            .fold(0, |index, &(_, n)| index * (n + 1) + n)
+    }
+
+    /// Lists the characters that may follow `state` at `index`, or start a
+    /// password if it is `None`, and the states they lead to.
+    fn next_states(
+        &self,
+        state: Option<usize>,
+        index: usize,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let (last, run, ascending, missing) = match state {
+            Some(state) => {
+                let missing = state % self.missing;
+                let rest = state / self.missing;
+
+                (
+                    Some(rest / 2 / self.runs),
+                    rest / 2 % self.runs,
+                    rest % 2 == 1,
+                    missing,
+                )
+            }
+            None => (None, 0, false, self.initial_missing()),
+        };
+
+        (0..self.charset.len()).filter_map(move |next| {
+            let c = self.charset[next];
+            let last_char = last.map(|last| self.charset[last]);
+            let same_class = last_char.is_some_and(|l| char_class(l) == char_class(c));
+            let next_run = if same_class { run + 1 } else { 1 };
+            let next_ascending = last_char.is_some_and(|l| ascends(l, c));
+
+            if self.constraints.no_repeat && last_char == Some(c)
+                || self.constraints.no_sequences && ascending && next_ascending
+                || self.constraints.max_run.is_some_and(|n| next_run > n)
+                || !self.constraints.allows(self.password_length, index, c)
+            {
+                return None;
+            }
+
+            Some((
+                next,
+                self.state(next, next_run, next_ascending, self.fill(missing, next)),
+            ))
+        })
+    }
+
+    /// Returns the missing characters after the character at `next` of the
+    /// charset, which counts toward every set that contains it.
+    fn fill(&self, missing: usize, next: usize) -> usize {
+        self.places[next]
+            .iter()
+            .filter(|&&(radix, n)| !(missing / radix).is_multiple_of(n + 1))
+            .fold(missing, |filled, &(radix, _)| filled - radix)
+    }
+
+    /// Returns `log2` of the number of valid passwords.
+    fn log2_count(&self) -> f64 {
+        let Some(last) = self.counts.last() else {
+            return if self.initial_missing() == 0 {
+                0.0
+            } else {
+                f64::NEG_INFINITY
+            };
+        };
+        let count: f64 = self.next_states(None, 0).map(|(_, next)| last[next]).sum();
+
+        count.log2() + self.scales[self.scales.len() - 1]
+    }
+
+    /// Lists every valid password.
+    fn list(&self) -> Vec<Vec<u8>> {
+        let mut passwords = Vec::new();
+
+        if self.counts.is_empty() {
+            if self.initial_missing() == 0 {
+                passwords.push(Vec::new());
+            }
+
+            return passwords;
+        }
+
+        self.list_from(None, &mut Vec::new(), &mut passwords);
+
+        passwords
+    }
+
+    /// Appends every valid password that starts with `prefix`, which leads
+    /// to `state`, to `passwords`.
+    fn list_from(&self, state: Option<usize>, prefix: &mut Vec<u8>, passwords: &mut Vec<Vec<u8>>) {
+        let remaining = self.counts.len() - prefix.len();
+
+        if remaining == 0 {
+            passwords.push(prefix.clone());
+            return;
+        }
+
+        let choices: Vec<(usize, usize)> = self.next_states(state, prefix.len()).collect();
+
+        for (next, next_state) in choices {
+            if self.counts[remaining - 1][next_state] > 0.0 {
+                prefix.push(self.charset[next]);
+                self.list_from(Some(next_state), prefix, passwords);
+                prefix.pop();
+            }
+        }
+    }
+
+    /// Picks one of the valid passwords uniformly at random.
+    pub(crate) fn sample<R: Rng>(&self, rng: &mut R) -> Result<Vec<u8>, Error> {
+        let mut password = Vec::with_capacity(self.counts.len());
+        let mut state = None;
+
+        for counts in self.counts.iter().rev() {
+            let choices: Vec<(usize, usize)> = self.next_states(state, password.len()).collect();
+            let weights = WeightedIndex::new(choices.iter().map(|&(_, next)| counts[next]))
+                .map_err(|_| Error::ConstraintsUnsatisfiable)?;
+            let (next, next_state) = choices[weights.sample(rng)];
+
+            password.push(self.charset[next]);
+            state = Some(next_state);
+        }
+
+        Ok(password)
+    }
+}
+
+/// Counts the valid passwords of `password_length` characters of `charset`
+/// with at least the given extra characters, in bits.
+pub(crate) fn log2_count_constrained(
+    password_length: usize,
+    charset: &[u8],
+    extra_charset: &[u8],
+    constraints: &Constraints,
+) -> Result<f64, Error> {
+    let bits = Counter::new(password_length, charset, extra_charset, constraints)?.log2_count();
+
+    if bits == f64::NEG_INFINITY {
+        return Err(Error::ConstraintsUnsatisfiable);
+    }
+
+    Ok(bits)
+}
+
+/// Picks one of the passwords counted by [`log2_count_constrained`]
+/// uniformly at random.
+pub(crate) fn sample_constrained<R: Rng>(
+    password_length: usize,
+    charset: &[u8],
+    extra_charset: &[u8],
+    constraints: &Constraints,
+    rng: &mut R,
+) -> Result<Vec<u8>, Error> {
+    Counter::new(password_length, charset, extra_charset, constraints)?.sample(rng)
+}
+
+/// Lists the passwords counted by [`log2_count_constrained`].
+pub(crate) fn list_constrained(
+    password_length: usize,
+    charset: &[u8],
+    extra_charset: &[u8],
+    constraints: &Constraints,
+) -> Result<Vec<Vec<u8>>, Error> {
+    Ok(Counter::new(password_length, charset, extra_charset, constraints)?.list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    /// Counts the valid passwords by enumerating every string.
+    fn brute_force(
+        password_length: usize,
+        charset: &[u8],
+        extra_charset: &[u8],
+        constraints: &Constraints,
+    ) -> usize {
+        let mut count = 0;
+        let total = charset.len().pow(password_length as u32);
+
+        for mut index in 0..total {
+            let password: Vec<u8> = (0..password_length)
+                .map(|_| {
+                    let c = charset[index % charset.len()];
+                    index /= charset.len();
+                    c
+                })
+                .collect();
+
+            if is_valid(&password, extra_charset, constraints) {
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    fn is_valid(password: &[u8], extra_charset: &[u8], constraints: &Constraints) -> bool {
+        let has_extras = calculate_char_multiplicity_pairs(extra_charset)
+            .iter()
+            .all(|&(c, n)| password.iter().filter(|&&p| p == c).count() >= n);
+
+        has_extras && constraints.broken_rules(password).is_empty()
+    }
+
+    #[test]
+    fn test_broken_rules() {
+        let constraints = Constraints {
+            no_repeat: true,
+            no_sequences: true,
+            max_run: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(constraints.broken_rules(b"aB3!xY7-"), vec![]);
+        assert_eq!(constraints.broken_rules(b"aaB3"), vec![BrokenRule::Repeat]);
+        assert_eq!(
+            constraints.broken_rules(b"x123!"),
+            vec![BrokenRule::Sequence]
+        );
+        // Ascending across classes is not a sequence.
+        assert_eq!(constraints.broken_rules(b"x89:"), vec![]);
+        assert_eq!(constraints.broken_rules(b"Abdfh"), vec![BrokenRule::Run(3)]);
+        assert_eq!(Constraints::default().broken_rules(b"aaabc"), vec![]);
+
+        let constraints = Constraints {
+            first: Some(CharClass::Letter),
+            last: Some(CharClass::Alphanumeric),
+            forbidden: vec!["2:0O".parse().unwrap()],
+            ..Default::default()
+        };
+
+        assert_eq!(constraints.broken_rules(b"a1b2"), vec![]);
+        assert_eq!(
+            constraints.broken_rules(b"1O2!"),
+            vec![
+                BrokenRule::First(CharClass::Letter),
+                BrokenRule::Last(CharClass::Alphanumeric),
+                BrokenRule::Forbidden(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_positional_rules() {
+        assert_eq!("Letter".parse::<CharClass>().unwrap(), CharClass::Letter);
+        assert_eq!(CharClass::Alphanumeric.to_string(), "alnum");
+        assert!("vowel".parse::<CharClass>().is_err());
+
+        for (s, position, chars) in [
+            ("first:0O", Position::Start(0), &b"0O"[..]),
+            ("last:!?", Position::End(0), b"!?"),
+            ("3:a:b", Position::Start(2), b"a:b"),
+            ("-2:-", Position::End(1), b"-"),
+        ] {
+            assert_eq!(
+                s.parse::<ForbiddenChars>().unwrap(),
+                ForbiddenChars {
+                    position,
+                    chars: chars.to_vec()
+                }
+            );
+        }
+
+        for s in ["", "last", "last:", "0:a", "-0:a", "middle:a", "x:a"] {
+            assert!(s.parse::<ForbiddenChars>().is_err(), "{}", s);
+        }
+
+        assert_eq!(
+            "2:!@#".parse::<RequiredChars>().unwrap(),
+            RequiredChars {
+                count: 2,
+                chars: b"!@#".to_vec()
+            }
+        );
+
+        for s in ["!@#", "0:!@#", "2:", "two:!@#"] {
+            assert!(s.parse::<RequiredChars>().is_err(), "{}", s);
+        }
+
+        assert_eq!(Position::Start(2).index(3), Some(2));
+        assert_eq!(Position::Start(3).index(3), None);
+        assert_eq!(Position::End(1).index(3), Some(1));
+        assert_eq!(Position::End(3).index(3), None);
+    }
+
+    #[test]
+    fn test_sets() {
+        let constraints = Constraints {
+            required: vec![
+                "1:!".parse().unwrap(),
+                "2:ba".parse().unwrap(),
+                "1:ab".parse().unwrap(),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            constraints.sets(b"!!x"),
+            vec![(b"!".to_vec(), 2), (b"x".to_vec(), 1), (b"ab".to_vec(), 2)]
+        );
+        assert_eq!(
+            constraints.disjoint_sets(b"!!x"),
+            Some(constraints.sets(b"!!x"))
+        );
+        assert_eq!(constraints.disjoint_sets(b"a"), None);
+        assert_eq!(
+            Constraints::default().disjoint_sets(b"x"),
+            Some(vec![(b"x".to_vec(), 1)])
+        );
+        assert_eq!(
+            Constraints {
+                no_repeat: true,
+                ..constraints
+            }
+            .disjoint_sets(b"x"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_min_length() {
+        let required = |specs: &[&str]| Constraints {
+            required: specs.iter().map(|spec| spec.parse().unwrap()).collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(Constraints::default().min_length(b"!!x"), 3);
+        assert_eq!(required(&["1:!"]).min_length(b"!"), 1);
+        assert_eq!(required(&["2:ab", "1:c"]).min_length(b"a"), 3);
+        assert_eq!(required(&["1:ab", "1:bc"]).min_length(b""), 1);
+        assert_eq!(required(&["2:ab", "2:bc"]).min_length(b"c"), 3);
+        assert_eq!(required(&["2:ab", "3:bc", "1:a"]).min_length(b""), 4);
+    }
+
+    #[test]
+    fn test_log2_count_constrained() {
+        let charset = b"!123Aab";
+        let all = [
+            Constraints::default(),
+            Constraints {
+                no_repeat: true,
+                ..Default::default()
+            },
+            Constraints {
+                no_sequences: true,
+                ..Default::default()
+            },
+            Constraints {
+                max_run: Some(2),
+                ..Default::default()
+            },
+            Constraints {
+                no_repeat: true,
+                no_sequences: true,
+                max_run: Some(1),
+                ..Default::default()
+            },
+            Constraints {
+                first: Some(CharClass::Letter),
+                last: Some(CharClass::Alphanumeric),
+                forbidden: vec!["-2:1a".parse().unwrap()],
+                ..Default::default()
+            },
+            Constraints {
+                required: vec!["2:!1".parse().unwrap(), "1:Aa".parse().unwrap()],
+                ..Default::default()
+            },
+            Constraints {
+                no_repeat: true,
+                first: Some(CharClass::Symbol),
+                forbidden: vec!["2:!".parse().unwrap()],
+                ..Default::default()
+            },
+        ];
+
+        for constraints in &all {
+            for extra_charset in [&b""[..], b"!", b"11a"] {
+                for password_length in 3..=5 {
+                    let expected =
+                        brute_force(password_length, charset, extra_charset, constraints);
+                    let bits = log2_count_constrained(
+                        password_length,
+                        charset,
+                        extra_charset,
+                        constraints,
+                    )
+                    .unwrap_or(f64::NEG_INFINITY);
+
+                    assert!(
+                        bits == (expected as f64).log2()
+                            || (bits - (expected as f64).log2()).abs() < 1e-9,
+                        "{:?} {:?} {}",
+                        constraints,
+                        extra_charset,
+                        password_length
+                    );
+                }
+            }
+        }
+
+        let constraints = Constraints {
+            no_repeat: true,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            log2_count_constrained(2, b"a", b"", &constraints),
+            Err(Error::ConstraintsUnsatisfiable)
+        ));
+
+        // Large counts do not overflow.
+        assert!(
+            (log2_count_constrained(500, b"01", b"", &Constraints::default()).unwrap() - 500.0)
+                .abs()
+                < 1e-9
+        );
+
+        // Runs longer than the password cost nothing.
+        let constraints = Constraints {
+            max_run: Some(usize::MAX - 1),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            log2_count_constrained(4, b"01", b"", &constraints).unwrap(),
+            4.0
+        );
+
+        // Too many extra characters to keep track of fail instead of
+        // taking forever.
+        let constraints = Constraints {
+            no_repeat: true,
+            ..Default::default()
+        };
+        let charset: Vec<u8> = (b'!'..=b'~').collect();
+
+        assert!(matches!(
+            log2_count_constrained(20, &charset, b"!@#$%^&*()-_=+[]{}", &constraints),
+            Err(Error::ConstraintsTooComplex)
+        ));
+    }
+
+    #[test]
+    fn test_list_constrained() {
+        let constraints = Constraints {
+            no_sequences: true,
+            last: Some(CharClass::Digit),
+            ..Default::default()
+        };
+        let passwords = list_constrained(4, b"!123Aab", b"a", &constraints).unwrap();
+
+        assert_eq!(
+            passwords.len(),
+            brute_force(4, b"!123Aab", b"a", &constraints)
+        );
+        assert!(passwords
+            .iter()
+            .all(|password| is_valid(password, b"a", &constraints)));
+        assert!(passwords.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            list_constrained(0, b"ab", b"", &Constraints::default()).unwrap(),
+            vec![Vec::<u8>::new()]
+        );
+    }
+
+    #[test]
+    fn test_sample_constrained() {
+        let constraints = Constraints {
+            no_repeat: true,
+            no_sequences: true,
+            max_run: Some(2),
+            first: Some(CharClass::Letter),
+            last: Some(CharClass::Digit),
+            required: vec!["2:!1".parse().unwrap()],
+            ..Default::default()
+        };
+
+        for _ in 0..200 {
+            let password =
+                sample_constrained(8, b"!123Aab", b"!!", &constraints, &mut OsRng).unwrap();
+
+            assert_eq!(password.len(), 8);
+            assert!(is_valid(&password, b"!!", &constraints));
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use crate::Error;
+
+/// Guesses per second of the built-in attacker models.
+const DEFAULT_GUESS_RATES: [(&str, f64); 4] = [
+    // 100 guesses per hour against a rate-limited login form.
+    ("online-throttled", 100.0 / 3600.0),
+    // A login form or API without rate limiting.
+    ("online-unthrottled", 10.0),
+    // Stolen bcrypt hashes with a typical work factor.
+    ("offline-bcrypt", 1e4),
+    // Stolen MD5 or NTLM hashes on a GPU cluster.
+    ("offline-fast-hash", 1e12),
+];
+
+/// An attacker guessing passwords at a fixed rate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttackerModel {
+    /// Name of the model, such as `offline-bcrypt`.
+    pub name: String,
+
+    /// Number of guesses the attacker makes per second.
+    pub guesses_per_second: f64,
+}
+
+impl AttackerModel {
+    /// Returns the built-in models.
+    ///
+    /// # Returns
+    ///
+    /// The built-in models, from the slowest attacker to the fastest.
+    pub fn defaults() -> Vec<AttackerModel> {
+        DEFAULT_GUESS_RATES
+            .iter()
+            .map(|&(name, guesses_per_second)| AttackerModel {
+                name: name.to_string(),
+                guesses_per_second,
+            })
+            .collect()
+    }
+
+    /// Returns the built-in models with `overrides` applied.
+    ///
+    /// # Parameters
+    ///
+    /// - `overrides`: Models that replace the rate of the built-in model
+    ///   with the same name, or are appended if there is none.
+    ///
+    /// # Returns
+    ///
+    /// The built-in models, in order, followed by the added ones.
+    pub fn defaults_with(overrides: &[AttackerModel]) -> Vec<AttackerModel> {
+        let mut models = AttackerModel::defaults();
+
+        for model in overrides {
+            match models.iter_mut().find(|m| m.name == model.name) {
+                Some(existing) => existing.guesses_per_second = model.guesses_per_second,
+                None => models.push(model.clone()),
+            }
+        }
+
+        models
+    }
+
+    /// Estimates the average time this attacker needs to guess a password.
+    ///
+    /// # Parameters
+    ///
+    /// - `entropy`: Entropy of the password in bits.
+    ///
+    /// # Returns
+    ///
+    /// The average time in seconds.
+    pub fn crack_time(&self, entropy: f64) -> f64 {
+        estimate_guesses(entropy) / self.guesses_per_second
+    }
+}
+
+impl FromStr for AttackerModel {
+    type Err = Error;
+
+    /// Parses a `name=guesses_per_second` pair, such as
+    /// `offline-fast-hash=1e11`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidGuessRate(s.to_string());
+        let (name, rate) = s.split_once('=').ok_or_else(invalid)?;
+        let guesses_per_second = rate.trim().parse::<f64>().map_err(|_| invalid())?;
+
+        if name.trim().is_empty() || !guesses_per_second.is_finite() || guesses_per_second <= 0.0 {
+            return Err(invalid());
+        }
+
+        Ok(AttackerModel {
+            name: name.trim().to_string(),
+            guesses_per_second,
+        })
+    }
+}
+
+/// Estimates the average number of guesses needed to find a password.
+///
+/// # Parameters
+///
+/// - `entropy`: Entropy of the password in bits.
+///
+/// # Returns
+///
+/// The average number of guesses, `(2^entropy + 1) / 2`.
+pub fn estimate_guesses(entropy: f64) -> f64 {
+    (entropy - 1.0).exp2() + 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_guesses() {
+        assert_eq!(estimate_guesses(0.0), 1.0);
+        assert_eq!(estimate_guesses(1.0), 1.5);
+        assert_eq!(estimate_guesses(10.0), 512.5);
+        assert!(estimate_guesses(2000.0).is_infinite());
+    }
+
+    #[test]
+    fn test_attacker_model() {
+        let model = "offline-fast-hash=1e11".parse::<AttackerModel>().unwrap();
+
+        assert_eq!(model.name, "offline-fast-hash");
+        assert_eq!(model.guesses_per_second, 1e11);
+        assert_eq!(model.crack_time(11.0), 1024.5 / 1e11);
+
+        for s in ["offline", "=10", "x=", "x=fast", "x=0", "x=-1", "x=inf"] {
+            assert!(s.parse::<AttackerModel>().is_err(), "{}", s);
+        }
+
+        let models = AttackerModel::defaults_with(&[
+            model,
+            "gpu-farm=1e15".parse::<AttackerModel>().unwrap(),
+        ]);
+        let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "online-throttled",
+                "online-unthrottled",
+                "offline-bcrypt",
+                "offline-fast-hash",
+                "gpu-farm"
+            ]
+        );
+        assert_eq!(models[3].guesses_per_second, 1e11);
+    }
+}
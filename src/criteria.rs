@@ -1,5 +1,5 @@
 /// Defines criteria for password generation.
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum PasswordCriteria<'a> {
     /// Allows letters and digits.
     Alphanumeric,
@@ -22,3 +22,20 @@ pub enum PasswordCriteria<'a> {
     /// Uses a regex pattern.
     RegexPattern(&'a str),
 }
+
+impl std::str::FromStr for PasswordCriteria<'_> {
+    type Err = crate::Error;
+
+    /// Parses a preset name (`alphanumeric`, `uppercase`, `lowercase`,
+    /// `digits`, or `symbols`) into the corresponding criteria.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "alphanumeric" => Ok(PasswordCriteria::Alphanumeric),
+            "uppercase" => Ok(PasswordCriteria::UppercaseAndDigitsOnly),
+            "lowercase" => Ok(PasswordCriteria::LowercaseAndDigitsOnly),
+            "digits" => Ok(PasswordCriteria::DigitsOnly),
+            "symbols" => Ok(PasswordCriteria::AllPrintableChars),
+            _ => Err(crate::Error::UnknownPreset(s.to_string())),
+        }
+    }
+}
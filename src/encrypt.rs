@@ -0,0 +1,122 @@
+use age::armor::{ArmoredWriter, Format};
+use age::secrecy::SecretString;
+use age::{scrypt, x25519, Encryptor, Recipient};
+use randpass::Error;
+use std::io::Write;
+
+/// The recipients an output is encrypted to.
+pub(crate) struct Encryption {
+    recipients: Vec<Box<dyn Recipient>>,
+}
+
+impl Encryption {
+    /// Encrypts to one or more age X25519 public keys (`age1...`).
+    pub(crate) fn to_recipients(recipients: &[String]) -> Result<Self, Error> {
+        let recipients = recipients
+            .iter()
+            .map(|r| {
+                r.parse::<x25519::Recipient>()
+                    .map(|r| Box::new(r) as Box<dyn Recipient>)
+                    .map_err(|_| Error::InvalidRecipient(r.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Encryption { recipients })
+    }
+
+    /// Encrypts with an scrypt-wrapped passphrase.
+    pub(crate) fn with_passphrase(passphrase: SecretString) -> Self {
+        Encryption {
+            recipients: vec![Box::new(scrypt::Recipient::new(passphrase))],
+        }
+    }
+
+    /// Encrypts everything `write` produces and sends it to `output`,
+    /// optionally ASCII-armored.
+    ///
+    /// Returns `output` once the age stream has been finished.
+    pub(crate) fn encrypt<W, F>(&self, output: W, armor: bool, write: F) -> Result<W, Error>
+    where
+        W: Write,
+        F: FnOnce(&mut dyn Write) -> Result<(), Error>,
+    {
+        let encryptor = Encryptor::with_recipients(self.recipients.iter().map(|r| r.as_ref()))
+            .map_err(|e| Error::EncryptionFailed(e.to_string()))?;
+        let format = if armor {
+            Format::AsciiArmor
+        } else {
+            Format::Binary
+        };
+        let mut writer = encryptor.wrap_output(ArmoredWriter::wrap_output(output, format)?)?;
+
+        write(&mut writer)?;
+
+        Ok(writer.finish()?.finish()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use age::armor::ArmoredReader;
+    use age::{Decryptor, Identity};
+    use std::io::Read;
+    use std::iter;
+
+    fn decrypt(ciphertext: &[u8], identity: &dyn Identity) -> String {
+        let decryptor = Decryptor::new_buffered(ArmoredReader::new(ciphertext)).unwrap();
+        let mut plaintext = String::new();
+
+        decryptor
+            .decrypt(iter::once(identity))
+            .unwrap()
+            .read_to_string(&mut plaintext)
+            .unwrap();
+
+        plaintext
+    }
+
+    #[test]
+    fn test_encrypt_to_recipient() {
+        let identity = x25519::Identity::generate();
+        let encryption = Encryption::to_recipients(&[identity.to_public().to_string()]).unwrap();
+
+        for armor in [false, true] {
+            let ciphertext = encryption
+                .encrypt(Vec::new(), armor, |w| Ok(w.write_all(b"hunter2\n")?))
+                .unwrap();
+
+            assert_eq!(
+                ciphertext.starts_with(b"-----BEGIN AGE ENCRYPTED FILE-----"),
+                armor
+            );
+            assert_eq!(decrypt(&ciphertext, &identity), "hunter2\n");
+        }
+    }
+
+    #[test]
+    fn test_encrypt_with_passphrase() {
+        let passphrase = SecretString::from("correct horse battery staple".to_string());
+        let mut recipient = scrypt::Recipient::new(passphrase.clone());
+
+        // Keep the test fast; the default work factor takes seconds.
+        recipient.set_work_factor(10);
+
+        let encryption = Encryption {
+            recipients: vec![Box::new(recipient)],
+        };
+        let ciphertext = encryption
+            .encrypt(Vec::new(), true, |w| Ok(w.write_all(b"hunter2\n")?))
+            .unwrap();
+
+        assert_eq!(
+            decrypt(&ciphertext, &scrypt::Identity::new(passphrase)),
+            "hunter2\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_recipient() {
+        assert!(Encryption::to_recipients(&["age1invalid".to_string()]).is_err());
+    }
+}
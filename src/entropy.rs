@@ -0,0 +1,582 @@
+use randpass::{
+    estimate_guesses, AttackerModel, Constraints, EntropyBreakdown, Error, LengthDistribution,
+    PasswordLength,
+};
+use serde_json::{json, Value};
+
+const DURATION_UNITS: [(&str, f64); 6] = [
+    ("year", 31_557_600.0),
+    ("month", 2_629_800.0),
+    ("day", 86_400.0),
+    ("hour", 3_600.0),
+    ("minute", 60.0),
+    ("second", 1.0),
+];
+
+/// Formats a duration in seconds in the largest whole unit, up to years.
+pub(crate) fn format_duration(seconds: f64) -> String {
+    if seconds < 1.0 {
+        return "less than a second".to_string();
+    }
+
+    if !seconds.is_finite() {
+        return "forever".to_string();
+    }
+
+    let (unit, unit_seconds) = DURATION_UNITS
+        .iter()
+        .find(|&&(_, unit_seconds)| seconds >= unit_seconds)
+        .copied()
+        .unwrap_or(DURATION_UNITS[DURATION_UNITS.len() - 1]);
+    let count = (seconds / unit_seconds).round();
+
+    if count >= 1e6 {
+        format!("{:.1e} {}s", count, unit)
+    } else if count == 1.0 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+/// Formats a guess rate, per hour if it is below one per second.
+pub(crate) fn format_guess_rate(guesses_per_second: f64) -> String {
+    if guesses_per_second < 1.0 {
+        format!("{}/h", (guesses_per_second * 360_000.0).round() / 100.0)
+    } else if guesses_per_second < 1e4 {
+        format!("{}/s", guesses_per_second)
+    } else {
+        format!("{:e}/s", guesses_per_second)
+    }
+}
+
+/// Renders one line per attacker model with its guess rate and the
+/// average time it needs to guess a password with `entropy` bits.
+pub(crate) fn render_crack_times(entropy: f64, models: &[AttackerModel]) -> Vec<String> {
+    models
+        .iter()
+        .map(|model| {
+            format!(
+                "{:<20}{:>12}  {}",
+                model.name,
+                format_guess_rate(model.guesses_per_second),
+                format_duration(model.crack_time(entropy))
+            )
+        })
+        .collect()
+}
+
+fn format_extra_chars(breakdown: &EntropyBreakdown) -> String {
+    breakdown
+        .extra_char_multiplicities
+        .iter()
+        .map(|&(c, n)| format!("'{}' x {}", c as char, n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Renders each term of the entropy formula, the total, and how it
+/// compares to the threshold.
+pub(crate) fn render_entropy_report(
+    breakdown: &EntropyBreakdown,
+    threshold: f64,
+    suggested_length: &Result<usize, Error>,
+    models: &[AttackerModel],
+) -> String {
+    let length = breakdown.password_length;
+    let extra_size = breakdown.extra_charset_size();
+    let charset = String::from_utf8_lossy(&breakdown.charset);
+    let mut report = format!(
+        "{:<13}{}\n{:<13}{:?} ({} characters)\n",
+        "length",
+        length,
+        "charset",
+        charset,
+        breakdown.charset.len()
+    );
+
+    if extra_size > 0 {
+        let denominator = breakdown
+            .extra_char_multiplicities
+            .iter()
+            .map(|&(_, n)| format!("{}!", n))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        report.push_str(&format!(
+            "{:<13}{} ({} characters)\n\n",
+            "extra",
+            format_extra_chars(breakdown),
+            extra_size
+        ));
+        report.push_str(&format!(
+            "{:<13}{:<28}{:>10.2} bits\n",
+            "placement",
+            format!("log2(C({}, {}))", length, extra_size),
+            breakdown.placement_bits
+        ));
+        report.push_str(&format!(
+            "{:<13}{:<28}{:>10.2} bits\n",
+            "arrangement",
+            format!("log2({}! / ({}))", extra_size, denominator),
+            breakdown.arrangement_bits
+        ));
+    } else {
+        report.push('\n');
+    }
+
+    report.push_str(&format!(
+        "{:<13}{:<28}{:>10.2} bits\n",
+        "random",
+        format!(
+            "({} - {}) * log2({})",
+            length,
+            extra_size,
+            breakdown.charset.len()
+        ),
+        breakdown.random_bits
+    ));
+    report.push_str(&format!(
+        "{:<41}{:>10.2} bits\n\n",
+        "total",
+        breakdown.total_bits()
+    ));
+
+    report.push_str(&render_verdict(
+        breakdown.total_bits(),
+        threshold,
+        suggested_length,
+        models,
+    ));
+
+    report
+}
+
+/// Renders how `total` compares to the threshold and how long it takes to
+/// crack.
+fn render_verdict(
+    total: f64,
+    threshold: f64,
+    suggested_length: &Result<usize, Error>,
+    models: &[AttackerModel],
+) -> String {
+    let mut verdict = format!(
+        "{:<13}{} bits ({})\n",
+        "threshold",
+        threshold,
+        if total >= threshold { "met" } else { "not met" }
+    );
+
+    match suggested_length {
+        Ok(suggested_length) => {
+            verdict.push_str(&format!("{:<13}{}\n", "suggested", suggested_length));
+        }
+        Err(e) => verdict.push_str(&format!("{:<13}none ({})\n", "suggested", e)),
+    }
+
+    if !models.is_empty() {
+        verdict.push_str(&format!(
+            "\n{:<13}{:.3e} guesses on average\n",
+            "crack time",
+            estimate_guesses(total)
+        ));
+
+        for line in render_crack_times(total, models) {
+            verdict.push_str(&format!("{:<13}{}\n", "", line));
+        }
+    }
+
+    verdict
+}
+
+/// The entropy of passwords with a length from a range or following
+/// constraints, which the terms of the formula do not describe.
+pub(crate) struct LengthEntropy<'a> {
+    pub(crate) password_length: PasswordLength,
+    pub(crate) constraints: &'a Constraints,
+    pub(crate) charset: Vec<u8>,
+
+    /// Each length and its entropy.
+    pub(crate) entropies: Vec<(usize, f64)>,
+    pub(crate) total: f64,
+}
+
+fn describe_constraints(constraints: &Constraints) -> String {
+    let mut rules = Vec::new();
+
+    if constraints.no_repeat {
+        rules.push("no repeats".to_string());
+    }
+
+    if constraints.no_sequences {
+        rules.push("no sequences".to_string());
+    }
+
+    if let Some(max_run) = constraints.max_run {
+        rules.push(format!("at most {} of one class in a row", max_run));
+    }
+
+    if let Some(class) = constraints.first {
+        rules.push(format!("first {}", class));
+    }
+
+    if let Some(class) = constraints.last {
+        rules.push(format!("last {}", class));
+    }
+
+    for forbidden in &constraints.forbidden {
+        rules.push(format!(
+            "no {:?} at {}",
+            String::from_utf8_lossy(&forbidden.chars),
+            forbidden.position
+        ));
+    }
+
+    for required in &constraints.required {
+        rules.push(format!(
+            "at least {} of {:?}",
+            required.count,
+            String::from_utf8_lossy(&required.chars)
+        ));
+    }
+
+    rules.join(", ")
+}
+
+/// Renders the entropy of each length and of all of them.
+pub(crate) fn render_length_entropy_report(
+    length_entropy: &LengthEntropy,
+    threshold: f64,
+    suggested_length: &Result<usize, Error>,
+    models: &[AttackerModel],
+) -> String {
+    let LengthEntropy {
+        password_length,
+        constraints,
+        charset,
+        entropies,
+        total,
+    } = length_entropy;
+    let mut report = match password_length {
+        PasswordLength::Fixed(length) => format!("{:<13}{}\n", "length", length),
+        PasswordLength::Range { min, max, .. } => format!(
+            "{:<13}{}..{} ({}, {} lengths)\n",
+            "length",
+            min,
+            max,
+            distribution_name(password_length),
+            entropies.len()
+        ),
+    };
+
+    report.push_str(&format!(
+        "{:<13}{:?} ({} characters)\n",
+        "charset",
+        String::from_utf8_lossy(charset),
+        charset.len()
+    ));
+
+    if !constraints.is_empty() {
+        report.push_str(&format!(
+            "{:<13}{}\n",
+            "rules",
+            describe_constraints(constraints)
+        ));
+    }
+
+    report.push('\n');
+
+    let formula = match password_length {
+        PasswordLength::Fixed(_) => "log2(number of passwords)".to_string(),
+        _ if distribution_name(password_length) == "weighted" => "log2(sum of 2^bits)".to_string(),
+        _ => format!("log2({}) + average", entropies.len()),
+    };
+
+    if entropies.len() > 1 {
+        for &(length, bits) in entropies {
+            report.push_str(&format!(
+                "{:<41}{:>10.2} bits\n",
+                format!("length {}", length),
+                bits
+            ));
+        }
+    }
+
+    report.push_str(&format!(
+        "{:<13}{:<28}{:>10.2} bits\n\n",
+        "total", formula, total
+    ));
+    report.push_str(&render_verdict(*total, threshold, suggested_length, models));
+
+    report
+}
+
+fn distribution_name(password_length: &PasswordLength) -> &'static str {
+    match password_length {
+        PasswordLength::Fixed(_) => "fixed",
+        PasswordLength::Range {
+            distribution: LengthDistribution::Weighted,
+            ..
+        } => "weighted",
+        _ => "uniform",
+    }
+}
+
+/// Renders the length as an object of the same shape whether it is fixed
+/// or a range.
+fn length_json(password_length: &PasswordLength) -> Value {
+    json!({
+        "min": password_length.min(),
+        "max": password_length.max(),
+        "distribution": distribution_name(password_length),
+    })
+}
+
+fn crack_times_json(total: f64, models: &[AttackerModel]) -> Vec<Value> {
+    models
+        .iter()
+        .map(|model| {
+            json!({
+                "model": model.name,
+                "guesses_per_second": model.guesses_per_second,
+                "seconds": model.crack_time(total),
+            })
+        })
+        .collect()
+}
+
+/// Renders the same information as [`render_entropy_report`] as JSON.
+pub(crate) fn render_entropy_json(
+    breakdown: &EntropyBreakdown,
+    threshold: f64,
+    suggested_length: &Result<usize, Error>,
+    models: &[AttackerModel],
+) -> String {
+    let total = breakdown.total_bits();
+    let extra: Vec<_> = breakdown
+        .extra_char_multiplicities
+        .iter()
+        .map(|&(c, n)| json!({ "char": (c as char).to_string(), "count": n }))
+        .collect();
+
+    json!({
+        "length": length_json(&PasswordLength::Fixed(breakdown.password_length)),
+        "charset": String::from_utf8_lossy(&breakdown.charset),
+        "charset_size": breakdown.charset.len(),
+        "extra": extra,
+        "extra_size": breakdown.extra_charset_size(),
+        "terms": {
+            "placement": breakdown.placement_bits,
+            "arrangement": breakdown.arrangement_bits,
+            "random": breakdown.random_bits,
+        },
+        "bits": total,
+        "threshold": threshold,
+        "meets_threshold": total >= threshold,
+        "suggested_length": suggested_length.as_ref().ok(),
+        "suggested_length_error": suggested_length.as_ref().err().map(Error::to_string),
+        "guesses": estimate_guesses(total),
+        "crack_times": crack_times_json(total, models),
+    })
+    .to_string()
+}
+
+/// Renders the same information as [`render_length_entropy_report`] as
+/// JSON.
+pub(crate) fn render_length_entropy_json(
+    length_entropy: &LengthEntropy,
+    threshold: f64,
+    suggested_length: &Result<usize, Error>,
+    models: &[AttackerModel],
+) -> String {
+    let LengthEntropy {
+        password_length,
+        constraints,
+        charset,
+        entropies,
+        total,
+    } = length_entropy;
+    let lengths: Vec<_> = entropies
+        .iter()
+        .map(|&(length, bits)| json!({ "length": length, "bits": bits }))
+        .collect();
+
+    json!({
+        "length": length_json(password_length),
+        "charset": String::from_utf8_lossy(charset),
+        "charset_size": charset.len(),
+        "rules": {
+            "no_repeat": constraints.no_repeat,
+            "no_sequences": constraints.no_sequences,
+            "max_run": constraints.max_run,
+            "first": constraints.first.map(|class| class.to_string()),
+            "last": constraints.last.map(|class| class.to_string()),
+            "forbidden": constraints
+                .forbidden
+                .iter()
+                .map(|forbidden| json!({
+                    "position": forbidden.position.to_string(),
+                    "chars": String::from_utf8_lossy(&forbidden.chars),
+                }))
+                .collect::<Vec<_>>(),
+            "required": constraints
+                .required
+                .iter()
+                .map(|required| json!({
+                    "count": required.count,
+                    "chars": String::from_utf8_lossy(&required.chars),
+                }))
+                .collect::<Vec<_>>(),
+        },
+        "lengths": lengths,
+        "bits": total,
+        "threshold": threshold,
+        "meets_threshold": *total >= threshold,
+        "suggested_length": suggested_length.as_ref().ok(),
+        "suggested_length_error": suggested_length.as_ref().err().map(Error::to_string),
+        "guesses": estimate_guesses(*total),
+        "crack_times": crack_times_json(*total, models),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use randpass::{explain_entropy, PasswordCriteria};
+
+    #[test]
+    fn test_render_entropy_report() {
+        let breakdown = explain_entropy(8, &PasswordCriteria::DigitsOnly, Some(b"!!@")).unwrap();
+        let report = render_entropy_report(&breakdown, 72.0, &Ok(23), &[]);
+
+        assert!(report.contains("charset      \"!0123456789@\" (12 characters)\n"));
+        assert!(report.contains("extra        '!' x 2, '@' x 1 (3 characters)\n"));
+        assert!(report.contains("log2(3! / (2! 1!))"));
+        assert!(report.contains("(8 - 3) * log2(12)"));
+        assert!(report.contains("threshold    72 bits (not met)\n"));
+        assert!(report.ends_with("suggested    23\n"));
+
+        let breakdown = explain_entropy(20, &PasswordCriteria::Alphanumeric, None).unwrap();
+        let report = render_entropy_report(&breakdown, 72.0, &Ok(13), &AttackerModel::defaults());
+
+        assert!(!report.contains("placement"));
+        assert!(report.contains("threshold    72 bits (met)\n"));
+        assert!(report.contains("crack time   3.522e35 guesses on average\n"));
+        assert!(report.contains("offline-bcrypt             1e4/s  1.1e24 years\n"));
+    }
+
+    #[test]
+    fn test_render_entropy_json() {
+        let breakdown = explain_entropy(8, &PasswordCriteria::DigitsOnly, Some(b"!!@")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&render_entropy_json(
+            &breakdown,
+            72.0,
+            &Ok(23),
+            &AttackerModel::defaults(),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            value["length"],
+            json!({ "min": 8, "max": 8, "distribution": "fixed" })
+        );
+        assert_eq!(value["charset_size"], 12);
+        assert_eq!(value["extra"][0], json!({ "char": "!", "count": 2 }));
+        // Parsing JSON numbers does not round-trip the last bit.
+        assert!((value["terms"]["arrangement"].as_f64().unwrap() - 3_f64.log2()).abs() < 1e-9);
+        assert!((value["bits"].as_f64().unwrap() - breakdown.total_bits()).abs() < 1e-9);
+        assert_eq!(value["meets_threshold"], false);
+        assert_eq!(value["suggested_length"], 23);
+        assert_eq!(value["suggested_length_error"], Value::Null);
+        assert_eq!(value["crack_times"][2]["model"], "offline-bcrypt");
+        assert_eq!(value["crack_times"][2]["guesses_per_second"], 1e4);
+    }
+
+    #[test]
+    fn test_render_length_entropy_report() {
+        let constraints = Constraints::default();
+        let mut length_entropy = LengthEntropy {
+            password_length: "8..10".parse::<PasswordLength>().unwrap(),
+            constraints: &constraints,
+            charset: b"01".to_vec(),
+            entropies: vec![(8, 8.0), (9, 9.0), (10, 10.0)],
+            total: 10.58,
+        };
+        let report = render_length_entropy_report(&length_entropy, 72.0, &Ok(13), &[]);
+
+        assert!(report.starts_with("length       8..10 (uniform, 3 lengths)\n"));
+        assert!(!report.contains("rules"));
+        assert!(report.contains("length 9                                       9.00 bits\n"));
+        assert!(report.contains("total        log2(3) + average                10.58 bits\n"));
+
+        length_entropy.password_length = length_entropy
+            .password_length
+            .with_distribution(LengthDistribution::Weighted);
+
+        let value: serde_json::Value = serde_json::from_str(&render_length_entropy_json(
+            &length_entropy,
+            72.0,
+            &Err(Error::UnreachableEntropy("too few".to_string())),
+            &[],
+        ))
+        .unwrap();
+
+        assert_eq!(
+            value["length"],
+            json!({ "min": 8, "max": 10, "distribution": "weighted" })
+        );
+        assert_eq!(value["suggested_length"], Value::Null);
+        assert_eq!(
+            value["suggested_length_error"],
+            "no password length is long enough: too few"
+        );
+        assert_eq!(value["lengths"][2], json!({ "length": 10, "bits": 10.0 }));
+
+        let constraints = Constraints {
+            no_repeat: true,
+            max_run: Some(2),
+            ..Default::default()
+        };
+        let length_entropy = LengthEntropy {
+            password_length: PasswordLength::Fixed(4),
+            constraints: &constraints,
+            charset: b"ab".to_vec(),
+            entropies: vec![(4, 1.0)],
+            total: 1.0,
+        };
+        let report = render_length_entropy_report(
+            &length_entropy,
+            72.0,
+            &Err(Error::UnreachableEntropy("too few".to_string())),
+            &[],
+        );
+
+        assert!(
+            report.ends_with("suggested    none (no password length is long enough: too few)\n")
+        );
+        assert!(report.starts_with(
+            "length       4\ncharset      \"ab\" (2 characters)\n\
+             rules        no repeats, at most 2 of one class in a row\n\n\
+             total        log2(number of passwords)         1.00 bits\n"
+        ));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0.5), "less than a second");
+        assert_eq!(format_duration(1.0), "1 second");
+        assert_eq!(format_duration(90.0), "2 minutes");
+        assert_eq!(format_duration(86_400.0 * 3.0), "3 days");
+        assert_eq!(format_duration(31_557_600.0 * 250.0), "250 years");
+        assert_eq!(format_duration(31_557_600.0 * 4.2e9), "4.2e9 years");
+        assert_eq!(format_duration(f64::INFINITY), "forever");
+    }
+
+    #[test]
+    fn test_format_guess_rate() {
+        assert_eq!(format_guess_rate(100.0 / 3600.0), "100/h");
+        assert_eq!(format_guess_rate(10.0), "10/s");
+        assert_eq!(format_guess_rate(2.5e12), "2.5e12/s");
+    }
+}
@@ -1,42 +1,271 @@
-use std::fmt;
+use std::{fmt, io};
 
 /// Represents errors that can occur during password generation.
+///
+/// New variants may be added in minor releases.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
+    /// The operation was cancelled by the user.
+    Cancelled,
+
+    /// Counting the passwords that follow the constraints takes too long.
+    ConstraintsTooComplex,
+
+    /// No password follows the constraints.
+    ConstraintsUnsatisfiable,
+
     /// A generic error variant.
     Default,
 
+    /// The passphrase for encryption is empty.
+    EmptyPassphrase,
+
+    /// The output could not be encrypted, with the reason.
+    EncryptionFailed(String),
+
+    /// The `gpg` invocation failed, with its error output.
+    GpgFailed(String),
+
+    /// No `.gpg-id` file was found in the password store.
+    GpgIdNotFound,
+
+    /// The configuration file is malformed, with the reason.
+    InvalidConfig(String),
+
+    /// The accounts CSV could not be processed, with the reason.
+    InvalidCsv(String),
+
+    /// The provided forbidden characters are not of the form
+    /// `position:chars`.
+    InvalidForbiddenChars(String),
+
+    /// The provided guess rate is not of the form `name=guesses_per_second`.
+    InvalidGuessRate(String),
+
+    /// The provided secret key specification is invalid.
+    InvalidKeySpec(String),
+
+    /// The provided length is neither a number nor a range such as
+    /// `16..24`.
+    InvalidLength(String),
+
+    /// The provided metadata item is not of the form `key:value`.
+    InvalidMetadata(String),
+
+    /// The provided password store entry name is invalid.
+    InvalidPassName(String),
+
+    /// The provided age recipient is invalid.
+    InvalidRecipient(String),
+
     /// The provided regex pattern is invalid.
     InvalidRegex,
 
+    /// The provided required characters are not of the form
+    /// `count:chars`.
+    InvalidRequiredChars(String),
+
+    /// The provided resource name is not a valid RFC 1123 subdomain.
+    InvalidResourceName(String),
+
+    /// A line of the symbol-name table is malformed.
+    InvalidSymbolTable(String),
+
+    /// The Wi-Fi passphrase is not 8 to 63 printable ASCII characters.
+    InvalidWifiPassphrase,
+
+    /// The word list has fewer than two distinct words.
+    InvalidWordList(String),
+
+    /// An I/O operation failed.
+    Io(io::Error),
+
+    /// The option needs a fixed length rather than a range.
+    LengthRangeUnsupported(String),
+
+    /// No entry names are given to export.
+    NoEntries,
+
     /// No characters match the given criteria.
     NoValidChars,
 
+    /// The operation requires an interactive terminal.
+    NotATerminal,
+
+    /// The password store entry already exists.
+    PassEntryExists(String),
+
+    /// Neither `PASSWORD_STORE_DIR` nor `HOME` is set to locate the
+    /// password store.
+    PassStoreNotFound,
+
+    /// The entered passphrases do not match.
+    PassphraseMismatch,
+
     /// Indicates insufficient password entropy, with the calculated
     /// entropy value.
     PasswordEntropyInsufficient(f64),
 
+    /// Some passwords violate the policy, with their number.
+    PolicyViolated(usize),
+
+    /// The data could not be encoded as a QR code, with the reason.
+    QrEncodingFailed(String),
+
     /// The regex pattern matches no characters.
     RegexMatchesNoChars,
 
     /// The number of extra characters is greater than the requested
     /// password length.
     TooManyExtraChars,
+
+    /// More unique passwords are requested than exist, with the number
+    /// requested and the number that exist.
+    TooManyPasswords(usize, f64),
+
+    /// The character class name is not recognized.
+    UnknownCharClass(String),
+
+    /// The given preset name is not recognized.
+    UnknownPreset(String),
+
+    /// The requested profile is not defined in the configuration file.
+    UnknownProfile(String),
+
+    /// The minimum entropy is neither a number of bits nor a known
+    /// security level.
+    UnknownSecurityLevel(String),
+
+    /// No password length reaches the minimum entropy, with the reason.
+    UnreachableEntropy(String),
+
+    /// The QR code file extension is neither `.svg` nor `.png`.
+    UnsupportedQrFormat(String),
+
+    /// The sheet file extension is neither `.html` nor `.svg`.
+    UnsupportedSheetFormat(String),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Error::Cancelled => write!(f, "cancelled"),
+            Error::ConstraintsTooComplex => write!(
+                f,
+                "the constraints are too complex to count the passwords \
+                 (use fewer extra characters, a smaller '--max-run', or a shorter length)"
+            ),
+            Error::ConstraintsUnsatisfiable => {
+                write!(f, "no password of this length follows the constraints")
+            }
             Error::Default => write!(f, "error"),
+            Error::EmptyPassphrase => write!(f, "the passphrase must not be empty"),
+            Error::EncryptionFailed(message) => write!(f, "encryption failed: {}", message),
+            Error::GpgFailed(message) => write!(f, "gpg failed: {}", message),
+            Error::GpgIdNotFound => write!(f, "no .gpg-id found in the password store"),
+            Error::InvalidConfig(reason) => write!(f, "invalid configuration: {}", reason),
+            Error::InvalidCsv(message) => write!(f, "invalid CSV: {}", message),
+            Error::InvalidForbiddenChars(forbidden) => write!(
+                f,
+                "invalid forbidden characters '{}' (expected 'position:chars' such as 'last:!?')",
+                forbidden
+            ),
+            Error::InvalidGuessRate(rate) => write!(
+                f,
+                "invalid guess rate '{}' (expected 'name=guesses_per_second')",
+                rate
+            ),
+            Error::InvalidKeySpec(spec) => write!(f, "invalid key specification '{}'", spec),
+            Error::InvalidLength(length) => write!(
+                f,
+                "invalid length '{}' (expected a number or a range such as '16..24')",
+                length
+            ),
+            Error::InvalidMetadata(item) => {
+                write!(f, "invalid metadata '{}' (expected 'key:value')", item)
+            }
+            Error::InvalidPassName(name) => write!(f, "invalid password store entry '{}'", name),
+            Error::InvalidRecipient(recipient) => write!(f, "invalid recipient '{}'", recipient),
             Error::InvalidRegex => write!(f, "invalid regex pattern"),
+            Error::InvalidRequiredChars(required) => write!(
+                f,
+                "invalid required characters '{}' (expected 'count:chars' such as '2:!@#')",
+                required
+            ),
+            Error::InvalidResourceName(name) => write!(f, "invalid resource name '{}'", name),
+            Error::InvalidSymbolTable(line) => {
+                write!(f, "invalid symbol-name table entry '{}'", line)
+            }
+            Error::InvalidWifiPassphrase => write!(
+                f,
+                "a Wi-Fi passphrase must be 8 to 63 printable ASCII characters"
+            ),
+            Error::InvalidWordList(path) => {
+                write!(f, "word list '{}' has fewer than two distinct words", path)
+            }
+            Error::Io(e) => write!(f, "{}", e),
+            Error::LengthRangeUnsupported(option) => {
+                write!(f, "'{}' needs a fixed length, not a range", option)
+            }
+            Error::NoEntries => write!(f, "no entries to export (use '--entry' or '--entries')"),
             Error::NoValidChars => write!(f, "no valid characters left in the charset"),
+            Error::NotATerminal => write!(f, "this requires an interactive terminal"),
+            Error::PassEntryExists(name) => write!(
+                f,
+                "'{}' already exists in the password store (use '--force' to overwrite)",
+                name
+            ),
+            Error::PassStoreNotFound => write!(
+                f,
+                "cannot locate the password store (set PASSWORD_STORE_DIR or HOME)"
+            ),
+            Error::PassphraseMismatch => write!(f, "the passphrases do not match"),
             Error::PasswordEntropyInsufficient(entropy) => {
                 write!(f, "your password has only {:.2} bits of entropy", entropy)
             }
+            Error::PolicyViolated(count) => {
+                write!(f, "{} password(s) violate the policy", count)
+            }
+            Error::QrEncodingFailed(message) => write!(f, "cannot create QR code: {}", message),
             Error::RegexMatchesNoChars => {
                 write!(f, "no valid characters found for the provided regex")
             }
             Error::TooManyExtraChars => write!(f, "too many extra characters"),
+            Error::TooManyPasswords(requested, available) => write!(
+                f,
+                "cannot generate {} unique passwords, only {} exist",
+                requested, available
+            ),
+            Error::UnknownCharClass(name) => write!(
+                f,
+                "unknown character class '{}' (use 'upper', 'lower', 'letter', \
+                 'digit', 'alnum', or 'symbol')",
+                name
+            ),
+            Error::UnknownPreset(name) => write!(f, "unknown preset '{}'", name),
+            Error::UnknownProfile(name) => write!(f, "unknown profile '{}'", name),
+            Error::UnknownSecurityLevel(name) => write!(
+                f,
+                "unknown security level '{}' (use a number of bits or one of \
+                 'online', 'offline', 'master', and 'crypto-key')",
+                name
+            ),
+            Error::UnreachableEntropy(reason) => {
+                write!(f, "no password length is long enough: {}", reason)
+            }
+            Error::UnsupportedQrFormat(path) => {
+                write!(f, "unsupported QR code file '{}' (use .svg or .png)", path)
+            }
+            Error::UnsupportedSheetFormat(path) => {
+                write!(f, "unsupported sheet file '{}' (use .html or .svg)", path)
+            }
         }
     }
 }
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,234 @@
+use clap::ValueEnum;
+use randpass::Error;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+/// Password manager import formats.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum ExportFormat {
+    /// KeePass 2.x XML
+    KeepassXml,
+
+    /// Bitwarden CSV
+    BitwardenCsv,
+
+    /// Bitwarden unencrypted JSON
+    BitwardenJson,
+
+    /// 1Password CSV
+    #[value(name = "1password-csv")]
+    OnePasswordCsv,
+}
+
+/// Reads entry names from a file, one per line, skipping blank lines.
+pub(crate) fn read_entry_names(path: &Path) -> Result<Vec<String>, Error> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| l.to_string())
+        .collect())
+}
+
+/// Renders `(name, password)` entries in the given import format.
+pub(crate) fn render_export(format: ExportFormat, entries: &[(String, String)]) -> String {
+    match format {
+        ExportFormat::KeepassXml => render_keepass_xml(entries),
+        ExportFormat::BitwardenCsv => render_csv(
+            &[
+                "folder",
+                "favorite",
+                "type",
+                "name",
+                "notes",
+                "fields",
+                "reprompt",
+                "login_uri",
+                "login_username",
+                "login_password",
+                "login_totp",
+            ],
+            entries.iter().map(|(name, password)| {
+                vec!["", "", "login", name, "", "", "0", "", "", password, ""]
+            }),
+        ),
+        ExportFormat::BitwardenJson => render_bitwarden_json(entries),
+        ExportFormat::OnePasswordCsv => render_csv(
+            &["Title", "Website", "Username", "Password", "Notes"],
+            entries
+                .iter()
+                .map(|(name, password)| vec![name.as_str(), "", "", password, ""]),
+        ),
+    }
+}
+
+fn render_keepass_xml(entries: &[(String, String)]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n\
+         <KeePassFile>\n\
+         \t<Root>\n\
+         \t\t<Group>\n\
+         \t\t\t<Name>randpass</Name>\n",
+    );
+
+    for (name, password) in entries {
+        xml.push_str(&format!(
+            "\t\t\t<Entry>\n\
+             \t\t\t\t<String>\n\
+             \t\t\t\t\t<Key>Title</Key>\n\
+             \t\t\t\t\t<Value>{}</Value>\n\
+             \t\t\t\t</String>\n\
+             \t\t\t\t<String>\n\
+             \t\t\t\t\t<Key>Password</Key>\n\
+             \t\t\t\t\t<Value ProtectInMemory=\"True\">{}</Value>\n\
+             \t\t\t\t</String>\n\
+             \t\t\t</Entry>\n",
+            escape_xml(name),
+            escape_xml(password)
+        ));
+    }
+
+    xml.push_str("\t\t</Group>\n\t</Root>\n</KeePassFile>\n");
+
+    xml
+}
+
+fn render_bitwarden_json(entries: &[(String, String)]) -> String {
+    let items: Vec<_> = entries
+        .iter()
+        .map(|(name, password)| {
+            json!({
+                "type": 1,
+                "name": name,
+                "notes": null,
+                "favorite": false,
+                "login": {
+                    "uris": [],
+                    "username": null,
+                    "password": password,
+                    "totp": null,
+                },
+            })
+        })
+        .collect();
+    let export = json!({
+        "encrypted": false,
+        "folders": [],
+        "items": items,
+    });
+
+    format!("{:#}\n", export)
+}
+
+fn render_csv<'a>(header: &[&str], rows: impl Iterator<Item = Vec<&'a str>>) -> String {
+    let mut csv = header.join(",");
+
+    csv.push('\n');
+
+    for row in rows {
+        csv.push_str(
+            &row.iter()
+                .map(|field| escape_csv(field))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        csv.push('\n');
+    }
+
+    csv
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+pub(crate) fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) || field.starts_with(' ') || field.ends_with(' ') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries() -> Vec<(String, String)> {
+        vec![
+            ("db".to_string(), "a<b&\"c".to_string()),
+            ("api, prod".to_string(), "x\"y z".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml("a<b>&'\""), "a&lt;b&gt;&amp;&apos;&quot;");
+    }
+
+    #[test]
+    fn test_escape_csv() {
+        assert_eq!(escape_csv("plain"), "plain");
+
+        assert_eq!(escape_csv("a,b"), "\"a,b\"");
+
+        assert_eq!(escape_csv("a\"b"), "\"a\"\"b\"");
+
+        assert_eq!(escape_csv(" a"), "\" a\"");
+    }
+
+    #[test]
+    fn test_render_keepass_xml() {
+        let xml = render_export(ExportFormat::KeepassXml, &entries());
+
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("<Value>db</Value>"));
+        assert!(xml.contains("<Value ProtectInMemory=\"True\">a&lt;b&amp;&quot;c</Value>"));
+        assert!(xml.ends_with("</KeePassFile>\n"));
+    }
+
+    #[test]
+    fn test_render_bitwarden_csv() {
+        assert_eq!(
+            render_export(ExportFormat::BitwardenCsv, &entries()),
+            "folder,favorite,type,name,notes,fields,reprompt,login_uri,login_username,login_password,login_totp\n\
+             ,,login,db,,,0,,,\"a<b&\"\"c\",\n\
+             ,,login,\"api, prod\",,,0,,,\"x\"\"y z\",\n"
+        );
+    }
+
+    #[test]
+    fn test_render_bitwarden_json() {
+        let export: serde_json::Value =
+            serde_json::from_str(&render_export(ExportFormat::BitwardenJson, &entries())).unwrap();
+
+        assert_eq!(export["encrypted"], false);
+        assert_eq!(export["items"][0]["name"], "db");
+        assert_eq!(export["items"][0]["login"]["password"], "a<b&\"c");
+        assert_eq!(export["items"][1]["login"]["password"], "x\"y z");
+    }
+
+    #[test]
+    fn test_render_1password_csv() {
+        assert_eq!(
+            render_export(ExportFormat::OnePasswordCsv, &entries()),
+            "Title,Website,Username,Password,Notes\n\
+             db,,,\"a<b&\"\"c\",\n\
+             \"api, prod\",,,\"x\"\"y z\",\n"
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+use randpass::{
+    calculate_constrained_entropy, create_constrained_password, Constraints, Error,
+    PasswordCriteria,
+};
+use std::io::{self, IsTerminal, Write};
+use std::panic;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ENTROPY_BAR_WIDTH: usize = 40;
+
+/// What to do with the password once the user has made a choice.
+pub(crate) enum Outcome {
+    Print(String),
+    Copy(String),
+}
+
+/// The character classes and length selected in the interface.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Selection {
+    pub(crate) password_length: usize,
+    pub(crate) uppercase: bool,
+    pub(crate) lowercase: bool,
+    pub(crate) digits: bool,
+    pub(crate) symbols: bool,
+}
+
+impl Selection {
+    /// Derives the initial selection from the criteria given on the command
+    /// line.
+    pub(crate) fn new(password_length: usize, criteria: &PasswordCriteria) -> Self {
+        let (uppercase, lowercase, symbols) = match criteria {
+            PasswordCriteria::UppercaseAndDigitsOnly => (true, false, false),
+            PasswordCriteria::LowercaseAndDigitsOnly => (false, true, false),
+            PasswordCriteria::DigitsOnly => (false, false, false),
+            PasswordCriteria::AllPrintableChars => (true, true, true),
+            _ => (true, true, false),
+        };
+
+        Selection {
+            password_length,
+            uppercase,
+            lowercase,
+            digits: true,
+            symbols,
+        }
+    }
+
+    /// Returns the base character set of the selected classes.
+    pub(crate) fn charset(&self) -> Vec<u8> {
+        (b' '..=b'~')
+            .filter(|c| {
+                (self.uppercase && c.is_ascii_uppercase())
+                    || (self.lowercase && c.is_ascii_lowercase())
+                    || (self.digits && c.is_ascii_digit())
+                    || (self.symbols && !c.is_ascii_alphanumeric())
+            })
+            .collect()
+    }
+
+    /// Toggles a class, unless that would leave no class selected.
+    fn toggle(&mut self, key: char) {
+        let mut toggled = self.clone();
+
+        match key {
+            'u' => toggled.uppercase = !toggled.uppercase,
+            'l' => toggled.lowercase = !toggled.lowercase,
+            'd' => toggled.digits = !toggled.digits,
+            's' => toggled.symbols = !toggled.symbols,
+            _ => return,
+        }
+
+        if !toggled.charset().is_empty() {
+            *self = toggled;
+        }
+    }
+}
+
+type PanicHook = Box<dyn Fn(&panic::PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+/// Restores the terminal when dropped, including during unwinding.
+struct TerminalGuard {
+    /// The panic hook installed before the guard, reinstalled on drop.
+    previous_hook: Option<Arc<PanicHook>>,
+}
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        let previous_hook = Arc::new(panic::take_hook());
+        let hook = Arc::clone(&previous_hook);
+
+        // The panic message would otherwise be printed to the alternate
+        // screen and lost.
+        panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            hook(info);
+        }));
+
+        enable_raw_mode()?;
+        execute!(io::stderr(), EnterAlternateScreen, Hide)?;
+
+        Ok(TerminalGuard {
+            previous_hook: Some(previous_hook),
+        })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+
+        // The hook cannot be changed while panicking, and ours restores
+        // the terminal before calling the previous one anyway.
+        if thread::panicking() {
+            return;
+        }
+
+        // Dropping our hook releases its reference to the previous one.
+        drop(panic::take_hook());
+
+        if let Some(previous_hook) = self.previous_hook.take() {
+            match Arc::try_unwrap(previous_hook) {
+                Ok(hook) => panic::set_hook(hook),
+                Err(hook) => panic::set_hook(Box::new(move |info| hook(info))),
+            }
+        }
+    }
+}
+
+fn restore_terminal() {
+    let _ = execute!(io::stderr(), Show, LeaveAlternateScreen);
+    let _ = disable_raw_mode();
+}
+
+/// Renders an entropy bar of `width` cells, full at twice the threshold.
+pub(crate) fn entropy_bar(entropy: f64, threshold: f64, width: usize) -> String {
+    let filled = ((entropy / (2.0 * threshold)).clamp(0.0, 1.0) * width as f64) as usize;
+
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+fn entropy_color(entropy: f64, threshold: f64) -> Color {
+    if entropy < threshold {
+        Color::Red
+    } else if entropy < 1.5 * threshold {
+        Color::Yellow
+    } else {
+        Color::Green
+    }
+}
+
+fn checkbox(checked: bool) -> &'static str {
+    if checked {
+        "[x]"
+    } else {
+        "[ ]"
+    }
+}
+
+fn draw(selection: &Selection, password: &str, entropy: f64, threshold: f64) -> io::Result<()> {
+    let mut stderr = io::stderr();
+
+    queue!(
+        stderr,
+        Clear(ClearType::All),
+        MoveTo(0, 0),
+        Print("randpass\r\n\r\n"),
+        Print(format!("  Password:  {}\r\n\r\n", password)),
+        Print(format!(
+            "  Length:    {}  (arrow keys to adjust)\r\n",
+            selection.password_length
+        )),
+        Print(format!(
+            "  Classes:   {} uppercase (u)  {} lowercase (l)  {} digits (d)  {} symbols (s)\r\n",
+            checkbox(selection.uppercase),
+            checkbox(selection.lowercase),
+            checkbox(selection.digits),
+            checkbox(selection.symbols)
+        )),
+        Print("  Entropy:   "),
+        SetForegroundColor(entropy_color(entropy, threshold)),
+        Print(entropy_bar(entropy, threshold, ENTROPY_BAR_WIDTH)),
+        ResetColor,
+        Print(format!(" {:.2} bits\r\n\r\n", entropy)),
+        Print("  r: regenerate  Enter: print  c: copy  q: quit\r\n"),
+    )?;
+
+    stderr.flush()
+}
+
+/// Runs the interactive interface on stderr until the user prints, copies,
+/// or quits. The entropy bar is colored against `threshold`, and a change
+/// that leaves no password following the criteria and `constraints` is
+/// ignored.
+///
+/// Returns `Err(Error::Cancelled)` if the user quits.
+pub(crate) fn run_interactive(
+    mut selection: Selection,
+    extra_charset: &[u8],
+    constraints: &Constraints,
+    threshold: f64,
+) -> Result<Outcome, Error> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        return Err(Error::NotATerminal);
+    }
+
+    let _guard = TerminalGuard::enter()?;
+    let mut regenerate = true;
+    let mut password = String::new();
+    let mut previous = selection.clone();
+    let min_length = constraints.min_length(extra_charset).max(1);
+
+    loop {
+        let charset = selection.charset();
+        let criteria = PasswordCriteria::BaseCharset(&charset);
+
+        if regenerate {
+            match create_constrained_password(
+                selection.password_length,
+                &criteria,
+                Some(extra_charset),
+                constraints,
+            ) {
+                Ok(created) => password = created,
+                Err(_) if selection != previous => {
+                    selection = previous.clone();
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+
+            regenerate = false;
+            previous = selection.clone();
+        }
+
+        let entropy = calculate_constrained_entropy(
+            selection.password_length,
+            &criteria,
+            Some(extra_charset),
+            constraints,
+        )?;
+
+        draw(&selection, &password, entropy, threshold)?;
+
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+
+        match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(Error::Cancelled)
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Err(Error::Cancelled),
+            KeyCode::Enter => return Ok(Outcome::Print(password)),
+            KeyCode::Char('c') => return Ok(Outcome::Copy(password)),
+            KeyCode::Char('r') | KeyCode::Char(' ') => regenerate = true,
+            KeyCode::Up | KeyCode::Right => {
+                selection.password_length += 1;
+                regenerate = true;
+            }
+            KeyCode::Down | KeyCode::Left if selection.password_length > min_length => {
+                selection.password_length -= 1;
+                regenerate = true;
+            }
+            KeyCode::Char(c) => {
+                selection.toggle(c);
+                regenerate = selection != previous;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Shows `text` on the alternate screen until `timeout` elapses or a key
+/// is pressed, so that nothing remains in the scrollback afterwards.
+pub(crate) fn show_ephemeral(text: &str, timeout: Duration) -> Result<(), Error> {
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        return Err(Error::NotATerminal);
+    }
+
+    let _guard = TerminalGuard::enter()?;
+    let deadline = Instant::now() + timeout;
+    let mut stderr = io::stderr();
+
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        queue!(
+            stderr,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            Print(text.trim_end_matches('\n').replace('\n', "\r\n")),
+            Print(format!(
+                "\r\n\r\n  hiding in {}s (press any key to hide now)\r\n",
+                remaining.as_millis().div_ceil(1000)
+            )),
+        )?;
+        stderr.flush()?;
+
+        if event::poll(remaining.min(Duration::from_secs(1)))? {
+            if let Event::Key(KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            }) = event::read()?
+            {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selection_from_criteria() {
+        let selection = Selection::new(20, &PasswordCriteria::Alphanumeric);
+        assert_eq!(
+            selection.charset(),
+            (b'0'..=b'9')
+                .chain(b'A'..=b'Z')
+                .chain(b'a'..=b'z')
+                .collect::<Vec<u8>>()
+        );
+
+        let selection = Selection::new(20, &PasswordCriteria::DigitsOnly);
+        assert_eq!(selection.charset(), (b'0'..=b'9').collect::<Vec<u8>>());
+
+        let selection = Selection::new(20, &PasswordCriteria::AllPrintableChars);
+        assert_eq!(selection.charset(), (b' '..=b'~').collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_selection_toggle() {
+        let mut selection = Selection::new(20, &PasswordCriteria::DigitsOnly);
+
+        selection.toggle('d');
+        assert!(selection.digits);
+
+        selection.toggle('u');
+        selection.toggle('d');
+        assert_eq!(selection.charset(), (b'A'..=b'Z').collect::<Vec<u8>>());
+
+        selection.toggle('x');
+        assert_eq!(selection.charset(), (b'A'..=b'Z').collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_entropy_bar() {
+        assert_eq!(entropy_bar(0.0, 72.0, 4), "░░░░");
+
+        assert_eq!(entropy_bar(72.0, 72.0, 4), "██░░");
+        assert_eq!(entropy_bar(72.0, 128.0, 4), "█░░░");
+
+        assert_eq!(entropy_bar(1000.0, 72.0, 4), "████");
+    }
+}
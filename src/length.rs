@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use crate::Error;
+
+/// How a length is picked from a range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LengthDistribution {
+    /// Every length is equally likely.
+    Uniform,
+
+    /// Lengths are weighted by the number of passwords of each length, so
+    /// every password of the range is equally likely.
+    Weighted,
+}
+
+/// A fixed password length, or an inclusive range to pick one from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PasswordLength {
+    /// Always this length.
+    Fixed(usize),
+
+    /// A length from `min` to `max`, both inclusive.
+    Range {
+        min: usize,
+        max: usize,
+        distribution: LengthDistribution,
+    },
+}
+
+impl PasswordLength {
+    /// Returns the shortest possible length.
+    pub fn min(&self) -> usize {
+        match *self {
+            PasswordLength::Fixed(length) => length,
+            PasswordLength::Range { min, .. } => min,
+        }
+    }
+
+    /// Returns the longest possible length.
+    pub fn max(&self) -> usize {
+        match *self {
+            PasswordLength::Fixed(length) => length,
+            PasswordLength::Range { max, .. } => max,
+        }
+    }
+
+    /// Returns the same length with a range picked by `distribution`.
+    pub fn with_distribution(self, distribution: LengthDistribution) -> Self {
+        match self {
+            PasswordLength::Fixed(_) => self,
+            PasswordLength::Range { min, max, .. } => PasswordLength::Range {
+                min,
+                max,
+                distribution,
+            },
+        }
+    }
+}
+
+impl From<usize> for PasswordLength {
+    fn from(length: usize) -> Self {
+        PasswordLength::Fixed(length)
+    }
+}
+
+impl FromStr for PasswordLength {
+    type Err = Error;
+
+    /// Parses a length such as `20`, or an inclusive range such as `16..24`
+    /// or `16..=24` picked uniformly.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidLength(s.to_string());
+        let parse = |n: &str| n.trim().parse::<usize>().map_err(|_| invalid());
+
+        let Some((min, max)) = s.split_once("..") else {
+            return Ok(PasswordLength::Fixed(parse(s)?));
+        };
+        let (min, max) = (parse(min)?, parse(max.strip_prefix('=').unwrap_or(max))?);
+
+        if min > max {
+            return Err(invalid());
+        }
+
+        Ok(PasswordLength::Range {
+            min,
+            max,
+            distribution: LengthDistribution::Uniform,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_password_length() {
+        assert_eq!(
+            "20".parse::<PasswordLength>().unwrap(),
+            PasswordLength::Fixed(20)
+        );
+
+        for s in ["16..24", "16..=24", " 16 .. 24 "] {
+            assert_eq!(
+                s.parse::<PasswordLength>().unwrap(),
+                PasswordLength::Range {
+                    min: 16,
+                    max: 24,
+                    distribution: LengthDistribution::Uniform,
+                }
+            );
+        }
+
+        for s in ["", "twenty", "-1", "24..16", "16..", "..24", "16...24"] {
+            assert!(s.parse::<PasswordLength>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_with_distribution() {
+        let length = "8..10".parse::<PasswordLength>().unwrap();
+
+        assert_eq!(
+            length.with_distribution(LengthDistribution::Weighted),
+            PasswordLength::Range {
+                min: 8,
+                max: 10,
+                distribution: LengthDistribution::Weighted,
+            }
+        );
+        assert_eq!(
+            PasswordLength::from(8).with_distribution(LengthDistribution::Weighted),
+            PasswordLength::Fixed(8)
+        );
+        assert_eq!((length.min(), length.max()), (8, 10));
+    }
+}
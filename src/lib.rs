@@ -1,13 +1,27 @@
+mod constraints;
+mod crack;
 mod criteria;
 mod errors;
+mod length;
+mod phonetic;
 mod util;
 
+use constraints::*;
+pub use constraints::{
+    BrokenRule, CharClass, Constraints, ExtraChars, ForbiddenChars, Position, RequiredChars,
+};
+pub use crack::*;
 pub use criteria::*;
 pub use errors::*;
+pub use length::*;
+pub use phonetic::*;
 use util::*;
 
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::seq::SliceRandom;
 use rand::{rngs::OsRng, Rng};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashSet};
 
 /// Creates a password.
 ///
@@ -43,6 +57,418 @@ pub fn create_password(
     Ok(password)
 }
 
+/// Creates a password that follows `constraints`, picked uniformly from
+/// all such passwords of the charset that contain at least the extra
+/// characters.
+///
+/// # Parameters
+///
+/// - `password_length`: Length of the password.
+/// - `criteria`: Password criteria.
+/// - `extra_charset`: Extra character set.
+/// - `constraints`: Rules on the characters of the password.
+///
+/// # Returns
+///
+/// `Ok(String)` with the generated password on success; `Err(Error)` on
+/// failure.
+pub fn create_constrained_password(
+    password_length: usize,
+    criteria: &PasswordCriteria,
+    extra_charset: Option<&[u8]>,
+    constraints: &Constraints,
+) -> Result<String, Error> {
+    if constraints.is_empty() {
+        return create_password(password_length, criteria, extra_charset);
+    }
+
+    let extra_charset = extra_charset.unwrap_or(&[]);
+    let charset = create_charset(
+        criteria,
+        Some(&[extra_charset, &constraints.required_chars()].concat()),
+    )?;
+
+    if password_length < constraints.min_length(extra_charset) {
+        return Err(Error::TooManyExtraChars);
+    }
+
+    if let Some(sets) = constraints.disjoint_sets(extra_charset) {
+        return create_password_with_sets(password_length, &charset, &sets, &mut OsRng);
+    }
+
+    let password_chars = sample_constrained(
+        password_length,
+        &charset,
+        extra_charset,
+        constraints,
+        &mut OsRng,
+    )?;
+
+    String::from_utf8(password_chars).map_err(|_| Error::Default)
+}
+
+/// Creates a password with a length picked from a range, skipping the
+/// lengths that no password follows `constraints` at.
+///
+/// # Parameters
+///
+/// - `password_length`: Fixed length or range of lengths of the password.
+/// - `criteria`: Password criteria.
+/// - `extra_charset`: Extra character set.
+/// - `constraints`: Rules on the characters of the password.
+///
+/// # Returns
+///
+/// `Ok(String)` with the generated password on success; `Err(Error)` on
+/// failure.
+pub fn create_password_with_length(
+    password_length: &PasswordLength,
+    criteria: &PasswordCriteria,
+    extra_charset: Option<&[u8]>,
+    constraints: &Constraints,
+) -> Result<String, Error> {
+    PasswordBatch::new(password_length, criteria, extra_charset, constraints)?.create_password()
+}
+
+/// Creates `count` passwords with lengths picked from a range, as
+/// [`create_password_with_length`] does, preparing the lengths and the
+/// counts of the constrained passwords once for the whole batch.
+///
+/// # Parameters
+///
+/// - `count`: Number of passwords.
+/// - `password_length`: Fixed length or range of lengths of the passwords.
+/// - `criteria`: Password criteria.
+/// - `extra_charset`: Extra character set.
+/// - `constraints`: Rules on the characters of the passwords.
+///
+/// # Returns
+///
+/// `Ok(Vec<String>)` with the generated passwords on success;
+/// `Err(Error)` on failure.
+pub fn create_passwords_with_length(
+    count: usize,
+    password_length: &PasswordLength,
+    criteria: &PasswordCriteria,
+    extra_charset: Option<&[u8]>,
+    constraints: &Constraints,
+) -> Result<Vec<String>, Error> {
+    let mut batch = PasswordBatch::new(password_length, criteria, extra_charset, constraints)?;
+
+    (0..count).map(|_| batch.create_password()).collect()
+}
+
+/// Creates passwords with the same criteria, keeping what they share.
+struct PasswordBatch<'a> {
+    criteria: &'a PasswordCriteria<'a>,
+    extra_charset: &'a [u8],
+    constraints: &'a Constraints,
+
+    /// The charset with the characters of the required sets, for the
+    /// constrained passwords.
+    charset: Vec<u8>,
+
+    /// The sets of [`Constraints::disjoint_sets`], if the constrained
+    /// passwords are created like those with extra characters.
+    sets: Option<Vec<(Vec<u8>, usize)>>,
+
+    /// The shortest length of a password that contains the extra
+    /// characters and the required sets.
+    min_length: usize,
+
+    /// The lengths that passwords can have.
+    lengths: Vec<usize>,
+
+    /// Weights of the lengths, which are otherwise equally likely.
+    weights: Option<WeightedIndex<f64>>,
+
+    /// The counts of the constrained passwords of each length picked so
+    /// far.
+    counters: BTreeMap<usize, Counter<'a>>,
+}
+
+impl<'a> PasswordBatch<'a> {
+    fn new(
+        password_length: &PasswordLength,
+        criteria: &'a PasswordCriteria<'a>,
+        extra_charset: Option<&'a [u8]>,
+        constraints: &'a Constraints,
+    ) -> Result<Self, Error> {
+        let (lengths, weights) = match *password_length {
+            PasswordLength::Fixed(length) => (vec![length], None),
+            PasswordLength::Range { distribution, .. } => {
+                let entropies = calculate_entropies_by_length(
+                    password_length,
+                    criteria,
+                    extra_charset,
+                    constraints,
+                )?;
+                let weights = match distribution {
+                    LengthDistribution::Uniform => None,
+                    LengthDistribution::Weighted => {
+                        let most = entropies
+                            .iter()
+                            .map(|&(_, e)| e)
+                            .fold(f64::NEG_INFINITY, f64::max);
+
+                        Some(
+                            WeightedIndex::new(entropies.iter().map(|&(_, e)| (e - most).exp2()))
+                                .map_err(|_| Error::Default)?,
+                        )
+                    }
+                };
+
+                (
+                    entropies.into_iter().map(|(length, _)| length).collect(),
+                    weights,
+                )
+            }
+        };
+        let extra_charset = extra_charset.unwrap_or(&[]);
+        let charset = if constraints.is_empty() {
+            Vec::new()
+        } else {
+            create_charset(
+                criteria,
+                Some(&[extra_charset, &constraints.required_chars()].concat()),
+            )?
+        };
+
+        Ok(PasswordBatch {
+            criteria,
+            extra_charset,
+            constraints,
+            charset,
+            sets: constraints.disjoint_sets(extra_charset),
+            min_length: constraints.min_length(extra_charset),
+            lengths,
+            weights,
+            counters: BTreeMap::new(),
+        })
+    }
+
+    fn create_password(&mut self) -> Result<String, Error> {
+        let length = match self.weights {
+            Some(ref weights) => self.lengths[weights.sample(&mut OsRng)],
+            None => self.lengths[OsRng.gen_range(0..self.lengths.len())],
+        };
+
+        if self.constraints.is_empty() {
+            return create_password(length, self.criteria, Some(self.extra_charset));
+        }
+
+        if length < self.min_length {
+            return Err(Error::TooManyExtraChars);
+        }
+
+        if let Some(ref sets) = self.sets {
+            return create_password_with_sets(length, &self.charset, sets, &mut OsRng);
+        }
+
+        let counter = match self.counters.entry(length) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Counter::new(
+                length,
+                &self.charset,
+                self.extra_charset,
+                self.constraints,
+            )?),
+        };
+
+        String::from_utf8(counter.sample(&mut OsRng)?).map_err(|_| Error::Default)
+    }
+}
+
+/// Creates `count` distinct passwords.
+///
+/// If there are at most [`MAX_LISTED_PASSWORDS`] passwords, they are all
+/// listed and drawn without replacement, each from a length picked by the
+/// length distribution among the lengths with passwords left; otherwise,
+/// duplicates are drawn again.
+///
+/// # Parameters
+///
+/// - `count`: Number of passwords.
+/// - `password_length`: Fixed length or range of lengths of the passwords.
+/// - `criteria`: Password criteria.
+/// - `extra_charset`: Extra character set.
+/// - `constraints`: Rules on the characters of the passwords.
+///
+/// # Returns
+///
+/// `Ok(Vec<String>)` with the generated passwords on success;
+/// `Err(Error::TooManyPasswords)` if fewer than `count` passwords exist.
+pub fn create_unique_passwords(
+    count: usize,
+    password_length: &PasswordLength,
+    criteria: &PasswordCriteria,
+    extra_charset: Option<&[u8]>,
+    constraints: &Constraints,
+) -> Result<Vec<String>, Error> {
+    let extras = extra_charset.unwrap_or(&[]);
+    let charset = create_charset(
+        criteria,
+        Some(&[extras, &constraints.required_chars()].concat()),
+    )?;
+    let sets = constraints.disjoint_sets(extras);
+    let lengths = password_length.min()..=password_length.max();
+    let bits: Vec<f64> = lengths
+        .clone()
+        .map(|length| match sets {
+            Some(ref sets) => Ok(log2_count_distinct_sets(length, &charset, sets)),
+            None => match log2_count_constrained(length, &charset, extras, constraints) {
+                Err(Error::ConstraintsUnsatisfiable) => Ok(f64::NEG_INFINITY),
+                bits => bits,
+            },
+        })
+        .collect::<Result<_, _>>()?;
+    let total = log2_sum_exp2(&bits).exp2().round();
+
+    if count as f64 > total {
+        return Err(Error::TooManyPasswords(count, total));
+    }
+
+    if total <= MAX_LISTED_PASSWORDS as f64 {
+        let distribution = match *password_length {
+            PasswordLength::Fixed(_) => LengthDistribution::Uniform,
+            PasswordLength::Range { distribution, .. } => distribution,
+        };
+        let mut listed = Vec::new();
+
+        for length in lengths {
+            let passwords = list_constrained(length, &charset, extras, constraints)?;
+
+            if !passwords.is_empty() {
+                listed.push(passwords);
+            }
+        }
+
+        let mut passwords = Vec::with_capacity(count);
+
+        // The length is picked as in `PasswordBatch`, among the lengths with
+        // passwords left.
+        while passwords.len() < count {
+            let index = match distribution {
+                LengthDistribution::Uniform => OsRng.gen_range(0..listed.len()),
+                LengthDistribution::Weighted => WeightedIndex::new(listed.iter().map(Vec::len))
+                    .map_err(|_| Error::Default)?
+                    .sample(&mut OsRng),
+            };
+            let picked = OsRng.gen_range(0..listed[index].len());
+            let password = listed[index].swap_remove(picked);
+
+            if listed[index].is_empty() {
+                listed.swap_remove(index);
+            }
+
+            passwords.push(String::from_utf8(password).map_err(|_| Error::Default)?);
+        }
+
+        return Ok(passwords);
+    }
+
+    let mut batch = PasswordBatch::new(password_length, criteria, extra_charset, constraints)?;
+    let mut seen = HashSet::with_capacity(count);
+    let mut passwords = Vec::with_capacity(count);
+
+    while passwords.len() < count {
+        let password = batch.create_password()?;
+
+        if seen.insert(password.clone()) {
+            passwords.push(password);
+        }
+    }
+
+    Ok(passwords)
+}
+
+/// The terms of the password entropy formula in
+/// `docs/password-entropy.md`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntropyBreakdown {
+    /// Length of the password.
+    pub password_length: usize,
+
+    /// Characters available at each random position, including the extra
+    /// characters.
+    pub charset: Vec<u8>,
+
+    /// Distinct extra characters and their multiplicities.
+    pub extra_char_multiplicities: Vec<(u8, usize)>,
+
+    /// Bits from the positions of the extra characters,
+    /// `log2(binom(l, |E|))`.
+    pub placement_bits: f64,
+
+    /// Bits from the order of the extra characters,
+    /// `log2(|E|! / prod(e_i!))`.
+    pub arrangement_bits: f64,
+
+    /// Bits from the remaining positions, `(l - |E|) * log2(|B|)`.
+    pub random_bits: f64,
+}
+
+impl EntropyBreakdown {
+    /// Returns the number of extra characters, `|E|`.
+    pub fn extra_charset_size(&self) -> usize {
+        self.extra_char_multiplicities.iter().map(|&(_, n)| n).sum()
+    }
+
+    /// Returns the total entropy in bits.
+    pub fn total_bits(&self) -> f64 {
+        self.placement_bits + self.arrangement_bits + self.random_bits
+    }
+}
+
+/// Breaks password entropy down into the terms of its formula.
+///
+/// # Parameters
+///
+/// - `password_length`: Length of the password.
+/// - `criteria`: Password criteria.
+/// - `extra_charset`: Extra character set.
+///
+/// # Returns
+///
+/// `Ok(EntropyBreakdown)` for valid inputs; `Err(Error)` otherwise.
+pub fn explain_entropy(
+    password_length: usize,
+    criteria: &PasswordCriteria,
+    extra_charset: Option<&[u8]>,
+) -> Result<EntropyBreakdown, Error> {
+    let charset = create_charset(criteria, extra_charset)?;
+    let multiplicities = calculate_char_multiplicity_pairs(extra_charset.unwrap_or(&[]));
+    let extra_charset_size = extra_charset.map_or(0, |extra_charset| extra_charset.len());
+
+    if password_length < extra_charset_size {
+        return Err(Error::TooManyExtraChars);
+    }
+
+    let (placement_bits, arrangement_bits) = if extra_charset_size == 0 {
+        (0.0, 0.0)
+    } else {
+        (
+            log2_binomial_coefficient(password_length as u64, extra_charset_size as u64),
+            log2_factorial(extra_charset_size as u64)
+                - multiplicities
+                    .iter()
+                    .map(|&(_, num)| log2_factorial(num as u64))
+                    .sum::<f64>(),
+        )
+    };
+    let random_bits =
+        (password_length - extra_charset_size) as f64 * (charset.len() as f64).log(2.0);
+
+    Ok(EntropyBreakdown {
+        password_length,
+        charset,
+        extra_char_multiplicities: multiplicities,
+        placement_bits,
+        arrangement_bits,
+        random_bits,
+    })
+}
+
 /// Calculates password entropy.
 ///
 /// # Parameters
@@ -53,68 +479,408 @@ pub fn create_password(
 ///
 /// # Returns
 ///
-/// `Some(f64)` for valid inputs; `None` otherwise.
+/// `Ok(f64)` for valid inputs; `Err(Error)` otherwise.
 pub fn calculate_entropy(
     password_length: usize,
     criteria: &PasswordCriteria,
     extra_charset: Option<&[u8]>,
 ) -> Result<f64, Error> {
-    let base_charset = create_charset(criteria, extra_charset)?;
-    let base_charset_size = base_charset.len();
+    Ok(explain_entropy(password_length, criteria, extra_charset)?.total_bits())
+}
+
+/// Calculates the entropy of passwords that follow `constraints`, the
+/// number of bits to count all such passwords of the charset that contain
+/// at least the extra characters.
+///
+/// # Parameters
+///
+/// - `password_length`: Length of the password.
+/// - `criteria`: Password criteria.
+/// - `extra_charset`: Extra character set.
+/// - `constraints`: Rules on the characters of the password.
+///
+/// # Returns
+///
+/// `Ok(f64)` for valid inputs; `Err(Error)` otherwise.
+pub fn calculate_constrained_entropy(
+    password_length: usize,
+    criteria: &PasswordCriteria,
+    extra_charset: Option<&[u8]>,
+    constraints: &Constraints,
+) -> Result<f64, Error> {
+    if constraints.is_empty() {
+        return calculate_entropy(password_length, criteria, extra_charset);
+    }
+
+    let extra_charset = extra_charset.unwrap_or(&[]);
+    let charset = create_charset(
+        criteria,
+        Some(&[extra_charset, &constraints.required_chars()].concat()),
+    )?;
+
+    if password_length < constraints.min_length(extra_charset) {
+        return Err(Error::TooManyExtraChars);
+    }
+
+    let Some(sets) = constraints.disjoint_sets(extra_charset) else {
+        return log2_count_constrained(password_length, &charset, extra_charset, constraints);
+    };
+    let bits = log2_count_distinct_sets(password_length, &charset, &sets);
+
+    if bits == f64::NEG_INFINITY {
+        return Err(Error::ConstraintsUnsatisfiable);
+    }
+
+    Ok(bits)
+}
+
+/// Calculates, for each set from the `i`-th on and each number of
+/// positions, the ways to fill those positions with at least the given
+/// number of characters of each of the disjoint `sets`, over the factorial
+/// of that number, in bits.
+fn log2_set_ways(password_length: usize, sets: &[(Vec<u8>, usize)]) -> Vec<Vec<f64>> {
+    let mut ways = vec![vec![f64::NEG_INFINITY; password_length + 1]; sets.len() + 1];
+
+    ways[sets.len()][0] = 0.0;
 
-    if let Some(extra_charset) = extra_charset {
-        let extra_char_multiplicities = calculate_char_multiplicities(extra_charset);
-        let extra_charset_size = extra_char_multiplicities.iter().sum::<usize>();
+    for (i, (chars, n)) in sets.iter().enumerate().rev() {
+        let size = (chars.len() as f64).log2();
 
-        if password_length < extra_charset_size {
-            return Err(Error::Default);
+        for taken in *n..=password_length {
+            let bits: Vec<f64> = (*n..=taken)
+                .map(|k| k as f64 * size - log2_factorial(k as u64) + ways[i + 1][taken - k])
+                .collect();
+
+            ways[i][taken] = log2_sum_exp2(&bits);
         }
+    }
 
-        Ok(
-            log2_binomial_coefficient(password_length as u64, extra_charset_size as u64)
-                + (log2_factorial(extra_charset_size.try_into().unwrap())
-                    - extra_char_multiplicities
-                        .iter()
-                        .map(|&num| log2_factorial(num as u64))
-                        .sum::<f64>())
-                + (password_length as u64 - extra_charset_size as u64) as f64
-                    * (base_charset_size as f64).log(2.0),
-        )
-    } else {
-        Ok((password_length as u64) as f64 * (base_charset_size as f64).log(2.0))
+    ways
+}
+
+/// Returns, for each number of positions taken by the disjoint `sets`, the
+/// number of passwords of `password_length` characters from `charset`
+/// whose sets take exactly those positions, in bits.
+fn log2_counts_by_taken(
+    password_length: usize,
+    charset: &[u8],
+    sets: &[(Vec<u8>, usize)],
+) -> Vec<f64> {
+    let ways = log2_set_ways(password_length, sets);
+    let others = charset.len() - sets.iter().map(|(chars, _)| chars.len()).sum::<usize>();
+
+    (0..=password_length)
+        .map(|taken| {
+            let rest = password_length - taken;
+            let others_bits = match rest {
+                0 => 0.0,
+                _ => rest as f64 * (others as f64).log2(),
+            };
+
+            log2_factorial(password_length as u64) + ways[0][taken] + others_bits
+                - log2_factorial(rest as u64)
+        })
+        .collect()
+}
+
+/// Counts the distinct passwords of `password_length` characters from
+/// `charset` with at least the given number of characters of each of the
+/// disjoint `sets`, in bits, which is negative infinity if there are none.
+///
+/// A password is counted once, by the number of its characters in each
+/// set, so that the count is the same as with any rule that holds for
+/// every password.
+fn log2_count_distinct_sets(
+    password_length: usize,
+    charset: &[u8],
+    sets: &[(Vec<u8>, usize)],
+) -> f64 {
+    log2_sum_exp2(&log2_counts_by_taken(password_length, charset, sets))
+}
+
+/// Picks an index with a probability proportional to `2^bits`.
+fn pick_by_bits<R: Rng>(bits: &[f64], rng: &mut R) -> Result<usize, Error> {
+    let most = bits.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    if most == f64::NEG_INFINITY {
+        return Err(Error::ConstraintsUnsatisfiable);
+    }
+
+    Ok(WeightedIndex::new(bits.iter().map(|b| (b - most).exp2()))
+        .map_err(|_| Error::Default)?
+        .sample(rng))
+}
+
+/// Creates a password with at least the given number of characters of each
+/// of the disjoint `sets` and the others from `charset`, picked uniformly
+/// among the passwords counted by [`log2_count_distinct_sets`].
+///
+/// The number of positions that each set takes is picked by its number of
+/// passwords first, then the characters and their positions.
+fn create_password_with_sets<R: Rng>(
+    password_length: usize,
+    charset: &[u8],
+    sets: &[(Vec<u8>, usize)],
+    rng: &mut R,
+) -> Result<String, Error> {
+    let ways = log2_set_ways(password_length, sets);
+    let others: Vec<u8> = charset
+        .iter()
+        .copied()
+        .filter(|c| !sets.iter().any(|(chars, _)| chars.contains(c)))
+        .collect();
+    let mut taken = pick_by_bits(&log2_counts_by_taken(password_length, charset, sets), rng)?;
+    let mut password_chars = Vec::with_capacity(password_length);
+
+    password_chars.extend((taken..password_length).map(|_| others[rng.gen_range(0..others.len())]));
+
+    for (i, (chars, n)) in sets.iter().enumerate() {
+        let size = (chars.len() as f64).log2();
+        let bits: Vec<f64> = (*n..=taken)
+            .map(|k| k as f64 * size - log2_factorial(k as u64) + ways[i + 1][taken - k])
+            .collect();
+        let k = n + pick_by_bits(&bits, rng)?;
+
+        password_chars.extend((0..k).map(|_| chars[rng.gen_range(0..chars.len())]));
+        taken -= k;
+    }
+
+    password_chars.shuffle(rng);
+
+    String::from_utf8(password_chars).map_err(|_| Error::Default)
+}
+
+/// Calculates the entropy of each length of a range, skipping the lengths
+/// that no password follows `constraints` at.
+///
+/// # Parameters
+///
+/// - `password_length`: Fixed length or range of lengths of the password.
+/// - `criteria`: Password criteria.
+/// - `extra_charset`: Extra character set.
+/// - `constraints`: Rules on the characters of the password.
+///
+/// # Returns
+///
+/// `Ok(Vec<(usize, f64)>)` with each possible length and its entropy;
+/// `Err(Error)` if there is none, or for invalid inputs.
+pub fn calculate_entropies_by_length(
+    password_length: &PasswordLength,
+    criteria: &PasswordCriteria,
+    extra_charset: Option<&[u8]>,
+    constraints: &Constraints,
+) -> Result<Vec<(usize, f64)>, Error> {
+    let mut entropies = Vec::new();
+    let mut impossible = None;
+
+    for length in password_length.min()..=password_length.max() {
+        match calculate_constrained_entropy(length, criteria, extra_charset, constraints) {
+            Ok(entropy) => entropies.push((length, entropy)),
+            Err(e @ (Error::ConstraintsUnsatisfiable | Error::TooManyExtraChars)) => {
+                impossible = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
     }
+
+    match impossible {
+        Some(e) if entropies.is_empty() => Err(e),
+        _ => Ok(entropies),
+    }
+}
+
+/// Calculates the entropy of passwords with a length picked from a range.
+///
+/// Passwords of different lengths never coincide, so a uniformly picked
+/// length adds `log2(max - min + 1)` bits to the average entropy of the
+/// lengths, and weighting the lengths by their number of passwords makes
+/// every password of the range equally likely. Lengths that no password
+/// follows `constraints` at are never picked, so they are left out.
+///
+/// # Parameters
+///
+/// - `password_length`: Fixed length or range of lengths of the password.
+/// - `criteria`: Password criteria.
+/// - `extra_charset`: Extra character set.
+/// - `constraints`: Rules on the characters of the password.
+///
+/// # Returns
+///
+/// `Ok(f64)` for valid inputs; `Err(Error)` otherwise.
+pub fn calculate_length_entropy(
+    password_length: &PasswordLength,
+    criteria: &PasswordCriteria,
+    extra_charset: Option<&[u8]>,
+    constraints: &Constraints,
+) -> Result<f64, Error> {
+    let distribution = match *password_length {
+        PasswordLength::Fixed(length) => {
+            return calculate_constrained_entropy(length, criteria, extra_charset, constraints)
+        }
+        PasswordLength::Range { distribution, .. } => distribution,
+    };
+    let entropies =
+        calculate_entropies_by_length(password_length, criteria, extra_charset, constraints)?;
+    let count = entropies.len() as f64;
+
+    Ok(match distribution {
+        LengthDistribution::Uniform => {
+            count.log2() + entropies.iter().map(|&(_, e)| e).sum::<f64>() / count
+        }
+        LengthDistribution::Weighted => {
+            let most = entropies
+                .iter()
+                .map(|&(_, e)| e)
+                .fold(f64::NEG_INFINITY, f64::max);
+
+            most + entropies
+                .iter()
+                .map(|&(_, e)| (e - most).exp2())
+                .sum::<f64>()
+                .log2()
+        }
+    })
 }
 
-/// The minimum entropy threshold for a secure password.
+/// The maximum number of passwords that [`create_unique_passwords`] lists
+/// to draw from without replacement.
+pub const MAX_LISTED_PASSWORDS: usize = 1 << 20;
+
+/// The default minimum entropy for a secure password, that of
+/// [`SecurityLevel::Offline`].
 pub const ENTROPY_THRESHOLD: f64 = 72.0;
 
+/// A named minimum entropy, by what the password protects.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SecurityLevel {
+    /// Passwords only exposed to rate-limited online guessing, 40 bits.
+    Online,
+
+    /// Passwords whose hashes may leak, 72 bits.
+    Offline,
+
+    /// Master passwords of password managers and disk encryption, 96 bits.
+    Master,
+
+    /// Secrets used directly as cryptographic keys, 128 bits.
+    CryptoKey,
+}
+
+impl SecurityLevel {
+    /// Returns the minimum entropy of the level in bits.
+    pub fn bits(self) -> f64 {
+        match self {
+            SecurityLevel::Online => 40.0,
+            SecurityLevel::Offline => ENTROPY_THRESHOLD,
+            SecurityLevel::Master => 96.0,
+            SecurityLevel::CryptoKey => 128.0,
+        }
+    }
+}
+
+impl std::str::FromStr for SecurityLevel {
+    type Err = Error;
+
+    /// Parses a level name (`online`, `offline`, `master`, or
+    /// `crypto-key`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "online" => Ok(SecurityLevel::Online),
+            "offline" => Ok(SecurityLevel::Offline),
+            "master" => Ok(SecurityLevel::Master),
+            "crypto-key" => Ok(SecurityLevel::CryptoKey),
+            _ => Err(Error::UnknownSecurityLevel(s.to_string())),
+        }
+    }
+}
+
 /// Suggests the minimum length for a secure password.
 ///
 /// # Parameters
 ///
 /// - `criteria`: Password criteria.
 /// - `extra_charset`: Extra character set.
+/// - `threshold`: Minimum entropy in bits, such as [`ENTROPY_THRESHOLD`] or
+///   [`SecurityLevel::bits`].
 ///
 /// # Returns
 ///
-/// `Some(usize)` with the suggested length; `None` if inputs are
-/// invalid.
+/// `Ok(usize)` with the shortest length whose entropy reaches `threshold`;
+/// `Err(Error)` if the inputs are invalid or no length is long enough.
 pub fn suggest_password_length(
     criteria: &PasswordCriteria,
     extra_charset: Option<&[u8]>,
-) -> Option<usize> {
-    for i in 1..1000 {
-        if let Ok(entropy) = calculate_entropy(i, criteria, extra_charset) {
-            if entropy >= ENTROPY_THRESHOLD {
-                return Some(i);
-            }
+    threshold: f64,
+) -> Result<usize, Error> {
+    let charset = create_charset(criteria, extra_charset)?;
+    let multiplicities = calculate_char_multiplicities(extra_charset.unwrap_or(&[]));
+    let extra_charset_size = multiplicities.iter().sum::<usize>();
+
+    if !threshold.is_finite() {
+        return Err(Error::UnreachableEntropy(format!(
+            "the minimum entropy is {}",
+            threshold
+        )));
+    }
+
+    if charset.len() < 2 {
+        return Err(Error::UnreachableEntropy(
+            "the charset has a single character, so every password is the same".to_string(),
+        ));
+    }
+
+    let bits_per_char = (charset.len() as f64).log2();
+    let arrangement_bits = log2_factorial(extra_charset_size as u64)
+        - multiplicities
+            .iter()
+            .map(|&num| log2_factorial(num as u64))
+            .sum::<f64>();
+    let entropy = |password_length: usize| {
+        let placement_bits = if extra_charset_size == 0 {
+            0.0
+        } else {
+            log2_binomial_coefficient(password_length as u64, extra_charset_size as u64)
+                + arrangement_bits
+        };
+
+        placement_bits + (password_length - extra_charset_size) as f64 * bits_per_char
+    };
+
+    // Entropy grows with the length, and the random positions alone reach
+    // the threshold at `high`, so the answer lies in `(low, high]`.
+    let mut low = extra_charset_size.max(1);
+
+    if entropy(low) >= threshold {
+        return Ok(low);
+    }
+
+    let high = extra_charset_size as f64 + (threshold / bits_per_char).ceil() + 1.0;
+
+    if high >= (u64::MAX / 2) as f64 {
+        return Err(Error::UnreachableEntropy(format!(
+            "{:e} bits would take more than {} characters",
+            threshold,
+            u64::MAX / 2
+        )));
+    }
+
+    let mut high = high as usize;
+
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+
+        if entropy(mid) >= threshold {
+            high = mid;
+        } else {
+            low = mid;
         }
     }
 
-    None
+    Ok(high)
 }
 
 #[cfg(test)]
+#[allow(clippy::unnecessary_cast)]
 mod tests {
     use super::*;
 
@@ -145,6 +911,307 @@ mod tests {
             .all(|c| password.contains(char::from(*c))));
     }
 
+    #[test]
+    fn test_create_password_with_length() {
+        for distribution in [LengthDistribution::Uniform, LengthDistribution::Weighted] {
+            let password_length = PasswordLength::Range {
+                min: 4,
+                max: 6,
+                distribution,
+            };
+
+            for _ in 0..100 {
+                let password = create_password_with_length(
+                    &password_length,
+                    &PasswordCriteria::DigitsOnly,
+                    Some(b"!"),
+                    &Constraints::default(),
+                )
+                .unwrap();
+
+                assert!((4..=6).contains(&password.len()));
+                assert!(password.contains('!'));
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_length_entropy() {
+        let criteria = PasswordCriteria::BaseCharset(b"ab");
+        let none = Constraints::default();
+
+        assert_eq!(
+            calculate_length_entropy(&PasswordLength::Fixed(3), &criteria, None, &none).unwrap(),
+            3.0
+        );
+
+        // 2 + 4 + 8 passwords, each length picked with probability 1/3.
+        let uniform = PasswordLength::Range {
+            min: 1,
+            max: 3,
+            distribution: LengthDistribution::Uniform,
+        };
+
+        assert!(
+            (calculate_length_entropy(&uniform, &criteria, None, &none).unwrap()
+                - (3_f64.log2() + 2.0))
+                .abs()
+                < 1e-9
+        );
+
+        // Every one of the 14 passwords equally likely.
+        let weighted = uniform.with_distribution(LengthDistribution::Weighted);
+
+        assert!(
+            (calculate_length_entropy(&weighted, &criteria, None, &none).unwrap() - 14_f64.log2())
+                .abs()
+                < 1e-9
+        );
+
+        // Length 1 is too short for the extra characters and is skipped.
+        assert!(
+            (calculate_length_entropy(&weighted, &criteria, Some(b"aa"), &none).unwrap()
+                - 7_f64.log2())
+            .abs()
+                < 1e-9
+        );
+        assert!(calculate_length_entropy(&weighted, &criteria, Some(b"aaaa"), &none).is_err());
+
+        let criteria = PasswordCriteria::BaseCharset(b"a1");
+        let constraints = Constraints {
+            first: Some(CharClass::Letter),
+            last: Some(CharClass::Digit),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            calculate_entropies_by_length(&uniform, &criteria, None, &constraints).unwrap(),
+            vec![(2, 0.0), (3, 1.0)]
+        );
+        assert!(
+            (calculate_length_entropy(&uniform, &criteria, None, &constraints).unwrap() - 1.5)
+                .abs()
+                < 1e-9
+        );
+
+        for _ in 0..20 {
+            let password =
+                create_password_with_length(&uniform, &criteria, None, &constraints).unwrap();
+
+            assert!(password.len() >= 2);
+        }
+    }
+
+    #[test]
+    fn test_create_constrained_password() {
+        let criteria = PasswordCriteria::BaseCharset(b"ab");
+        let constraints = Constraints {
+            no_repeat: true,
+            ..Default::default()
+        };
+
+        for _ in 0..20 {
+            let password = create_constrained_password(4, &criteria, None, &constraints).unwrap();
+
+            assert!(password == "abab" || password == "baba");
+        }
+
+        assert!(create_constrained_password(1, &criteria, Some(b"aa"), &constraints).is_err());
+
+        let required = Constraints {
+            required: vec!["2:!?".parse().unwrap()],
+            ..Default::default()
+        };
+
+        for _ in 0..20 {
+            let password =
+                create_constrained_password(4, &criteria, Some(b"-"), &required).unwrap();
+
+            assert_eq!(password.len(), 4);
+            assert!(password.contains('-'));
+            assert!(password.chars().filter(|c| "!?".contains(*c)).count() >= 2);
+        }
+
+        // Passwords with more characters of a set than required are not
+        // more likely.
+        let required = Constraints {
+            required: vec!["1:a".parse().unwrap()],
+            ..Default::default()
+        };
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+        for _ in 0..3000 {
+            *counts
+                .entry(create_constrained_password(2, &criteria, None, &required).unwrap())
+                .or_insert(0) += 1;
+        }
+
+        assert_eq!(counts.len(), 3);
+        assert!(
+            counts.values().all(|&n| (800..1200).contains(&n)),
+            "{counts:?}"
+        );
+
+        // A character counts toward every set that contains it.
+        let overlapping = Constraints {
+            required: vec!["1:ab".parse().unwrap(), "1:bc".parse().unwrap()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            create_constrained_password(1, &criteria, None, &overlapping).unwrap(),
+            "b"
+        );
+    }
+
+    #[test]
+    fn test_create_passwords_with_length() {
+        let criteria = PasswordCriteria::BaseCharset(b"ab");
+        let constraints = Constraints {
+            no_repeat: true,
+            ..Default::default()
+        };
+        let weighted = PasswordLength::Range {
+            min: 3,
+            max: 5,
+            distribution: LengthDistribution::Weighted,
+        };
+        let passwords =
+            create_passwords_with_length(50, &weighted, &criteria, Some(b"a"), &constraints)
+                .unwrap();
+
+        assert_eq!(passwords.len(), 50);
+        assert!(passwords.iter().all(|password| {
+            (3..=5).contains(&password.len())
+                && constraints.broken_rules(password.as_bytes()).is_empty()
+        }));
+        assert!(create_passwords_with_length(
+            1,
+            &PasswordLength::Fixed(2),
+            &PasswordCriteria::BaseCharset(b"a"),
+            None,
+            &constraints
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_create_unique_passwords() {
+        let criteria = PasswordCriteria::DigitsOnly;
+        let none = Constraints::default();
+        let length = PasswordLength::Fixed(2);
+
+        // Listed: every password of the space.
+        let mut passwords = create_unique_passwords(100, &length, &criteria, None, &none).unwrap();
+
+        passwords.sort();
+        passwords.dedup();
+        assert_eq!(passwords.len(), 100);
+        assert!(matches!(
+            create_unique_passwords(101, &length, &criteria, None, &none),
+            Err(Error::TooManyPasswords(101, total)) if total == 100.0
+        ));
+
+        // Only the passwords that contain the extra characters count.
+        assert!(matches!(
+            create_unique_passwords(20, &length, &criteria, Some(b"7"), &none),
+            Err(Error::TooManyPasswords(20, total)) if total == 19.0
+        ));
+
+        // Drawn again on duplicates.
+        let length = "8..9".parse::<PasswordLength>().unwrap();
+        let passwords = create_unique_passwords(1000, &length, &criteria, None, &none).unwrap();
+        let distinct: HashSet<&String> = passwords.iter().collect();
+
+        assert_eq!(distinct.len(), 1000);
+        assert!(passwords.iter().all(|p| (8..=9).contains(&p.len())));
+
+        // Sized without counting through every missing extra character.
+        let length = PasswordLength::Fixed(20);
+        let passwords =
+            create_unique_passwords(2, &length, &criteria, Some(b"abcdefghijklmnop"), &none)
+                .unwrap();
+
+        assert_eq!(passwords.len(), 2);
+
+        // Listed, but every length is equally likely.
+        let length = "1..2".parse::<PasswordLength>().unwrap();
+        let passwords = create_unique_passwords(40, &length, &criteria, None, &none).unwrap();
+        let short = passwords.iter().filter(|p| p.len() == 1).count();
+
+        assert!(short >= 8, "{short} of 40 passwords have one digit");
+    }
+
+    #[test]
+    fn test_log2_count_distinct_sets() {
+        let charset = b"abcdef";
+        let constraints = Constraints {
+            required: vec!["1:ab".parse().unwrap(), "2:c".parse().unwrap()],
+            ..Default::default()
+        };
+
+        for (extra_charset, constraints) in [
+            (b"".as_slice(), &constraints),
+            (b"dd", &constraints),
+            (b"ade", &Constraints::default()),
+        ] {
+            let sets = constraints.disjoint_sets(extra_charset).unwrap();
+
+            for length in 0..=6 {
+                let expected = log2_count_constrained(length, charset, extra_charset, constraints)
+                    .unwrap_or(f64::NEG_INFINITY);
+                let bits = log2_count_distinct_sets(length, charset, &sets);
+
+                assert!(
+                    bits == expected || (bits - expected).abs() < 1e-9,
+                    "{length}: {bits} != {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_constrained_entropy() {
+        let criteria = PasswordCriteria::BaseCharset(b"ab");
+        let constraints = Constraints {
+            no_repeat: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            calculate_constrained_entropy(4, &criteria, None, &constraints).unwrap(),
+            1.0
+        );
+        assert_eq!(
+            calculate_constrained_entropy(4, &criteria, None, &Constraints::default()).unwrap(),
+            calculate_entropy(4, &criteria, None).unwrap()
+        );
+        assert!(matches!(
+            calculate_constrained_entropy(2, &criteria, Some(b"aa"), &constraints),
+            Err(Error::ConstraintsUnsatisfiable)
+        ));
+
+        // A rule that every password follows leaves the entropy unchanged.
+        let criteria = PasswordCriteria::DigitsOnly;
+        let required = Constraints {
+            required: vec!["2:!@".parse().unwrap(), "1:#".parse().unwrap()],
+            ..Default::default()
+        };
+        let no_op = Constraints {
+            max_run: Some(1000),
+            ..required.clone()
+        };
+
+        for extra_charset in [None, Some(b"!".as_slice()), Some(b"$$")] {
+            let bits =
+                calculate_constrained_entropy(8, &criteria, extra_charset, &required).unwrap();
+            let expected =
+                calculate_constrained_entropy(8, &criteria, extra_charset, &no_op).unwrap();
+
+            assert!((bits - expected).abs() < 1e-9, "{bits} != {expected}");
+        }
+    }
+
     #[test]
     fn test_calculate_entropy() {
         assert_eq!(
@@ -184,13 +1251,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_explain_entropy() {
+        let breakdown = explain_entropy(10, &PasswordCriteria::DigitsOnly, Some(b"!@!")).unwrap();
+
+        assert_eq!(breakdown.charset, b"!0123456789@");
+        assert_eq!(
+            breakdown.extra_char_multiplicities,
+            vec![(b'!', 2), (b'@', 1)]
+        );
+        assert_eq!(breakdown.extra_charset_size(), 3);
+        assert_eq!(breakdown.placement_bits, log2_binomial_coefficient(10, 3));
+        assert_eq!(breakdown.arrangement_bits, 3_f64.log2());
+        assert_eq!(breakdown.random_bits, 7.0 * 12_f64.log2());
+        assert_eq!(
+            breakdown.total_bits(),
+            calculate_entropy(10, &PasswordCriteria::DigitsOnly, Some(b"!@!")).unwrap()
+        );
+
+        assert!(explain_entropy(2, &PasswordCriteria::DigitsOnly, Some(b"!@!")).is_err());
+    }
+
     #[test]
     fn test_suggest_password_length() {
         assert_eq!(
-            suggest_password_length(&PasswordCriteria::Alphanumeric, None),
-            Some(13)
+            suggest_password_length(&PasswordCriteria::Alphanumeric, None, ENTROPY_THRESHOLD)
+                .unwrap(),
+            13
+        );
+        assert_eq!(
+            suggest_password_length(
+                &PasswordCriteria::Alphanumeric,
+                None,
+                SecurityLevel::CryptoKey.bits()
+            )
+            .unwrap(),
+            22
+        );
+
+        // The search agrees with trying every length in turn.
+        for (criteria, extra_charset) in [
+            (PasswordCriteria::DigitsOnly, None),
+            (PasswordCriteria::AllPrintableChars, Some(&b"!!@"[..])),
+            (PasswordCriteria::BaseCharset(b"ab"), Some(&b"aaaaaaaa"[..])),
+        ] {
+            for threshold in [0.0, 1.0, 40.0, 72.0, 128.0, 256.0] {
+                let expected = (1..)
+                    .find(|&i| {
+                        calculate_entropy(i, &criteria, extra_charset)
+                            .is_ok_and(|entropy| entropy >= threshold)
+                    })
+                    .unwrap();
+
+                assert_eq!(
+                    suggest_password_length(&criteria, extra_charset, threshold).unwrap(),
+                    expected
+                );
+            }
+        }
+
+        assert_eq!(
+            suggest_password_length(&PasswordCriteria::DigitsOnly, None, 1.0e6).unwrap(),
+            301_030
         );
 
-        assert!(suggest_password_length(&PasswordCriteria::BaseCharset(b"a"), None).is_none());
+        for (criteria, threshold) in [
+            (PasswordCriteria::BaseCharset(b"a"), ENTROPY_THRESHOLD),
+            (PasswordCriteria::Alphanumeric, f64::INFINITY),
+            (PasswordCriteria::Alphanumeric, 1.0e300),
+        ] {
+            assert!(matches!(
+                suggest_password_length(&criteria, None, threshold),
+                Err(Error::UnreachableEntropy(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_security_level() {
+        assert_eq!("online".parse::<SecurityLevel>().unwrap().bits(), 40.0);
+        assert_eq!(
+            "offline".parse::<SecurityLevel>().unwrap().bits(),
+            ENTROPY_THRESHOLD
+        );
+        assert_eq!("crypto-key".parse::<SecurityLevel>().unwrap().bits(), 128.0);
+        assert!("paranoid".parse::<SecurityLevel>().is_err());
     }
 }
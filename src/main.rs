@@ -1,223 +1,1750 @@
+mod accounts;
+mod check;
+mod clipboard;
+mod config;
+mod encrypt;
+mod entropy;
+mod export;
+mod interactive;
+mod pass;
+mod phrase;
+mod qr;
+mod secret;
+mod sheet;
+mod token;
 mod util;
 
+use accounts::*;
+use check::*;
+use clipboard::*;
+use config::*;
+use encrypt::*;
+use entropy::*;
+use export::*;
+use interactive::*;
+use pass::*;
+use phrase::*;
+use qr::*;
+use secret::*;
+use sheet::*;
+use token::*;
 use util::*;
 
-use clap::Parser;
+use age::secrecy::SecretString;
+use clap::builder::{BoolishValueParser, Resettable, TypedValueParser};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use clap_complete::{Generator, Shell};
 use randpass::{
-    calculate_entropy, create_password, suggest_password_length, Error, PasswordCriteria,
-    ENTROPY_THRESHOLD,
+    calculate_constrained_entropy, calculate_entropies_by_length, calculate_length_entropy,
+    create_password, create_password_with_length, create_passwords_with_length,
+    create_unique_passwords, explain_entropy, spell_phonetically, suggest_password_length,
+    AttackerModel, CharClass, Constraints, Error, ExtraChars, ForbiddenChars, LengthDistribution,
+    PasswordCriteria, PasswordLength, RequiredChars, SecurityLevel, SymbolNames,
 };
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::str::FromStr;
+use std::time::Duration;
+use toml::Table;
 
 /// Password Generator
 #[derive(Parser)]
 #[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Use a profile from the configuration file
+    #[arg(short = 'p', long = "profile", global = true)]
+    profile: Option<String>,
+
+    #[command(flatten)]
+    generate: Args,
+}
+
+#[derive(clap::Args)]
+#[command(group(clap::ArgGroup::new("batch").args(["export_format", "sheet_file"])))]
+#[command(group(
+    clap::ArgGroup::new("encryption").args(["age_recipients", "encrypt_with_passphrase"])
+))]
+#[command(group(
+    clap::ArgGroup::new("spelling")
+        .args(["phonetic", "sheet_file"])
+        .multiple(true)
+))]
+#[command(group(
+    clap::ArgGroup::new("secrets")
+        .args(["k8s_secret", "docker_secrets"])
+        .multiple(true)
+))]
+#[command(group(
+    clap::ArgGroup::new("destination")
+        .args([
+            "k8s_secret",
+            "docker_secrets",
+            "pass_name",
+            "export_format",
+            "accounts_file",
+        ])
+        .multiple(true)
+))]
 struct Args {
-    /// Length of the password
-    #[arg(short = 'l', long = "length", default_value_t = 20)]
-    password_length: usize,
+    #[command(flatten)]
+    criteria: CriteriaArgs,
 
-    /// Use uppercase letters and digits only
+    #[command(flatten)]
+    min_entropy: ThresholdArgs,
+
+    #[command(flatten)]
+    guess_rates: GuessRateArgs,
+
+    /// Number of passwords to generate
     #[arg(
-        short = 'u',
-        long = "uppercase",
-        conflicts_with = "use_lowercase_and_digits_only",
-        conflicts_with = "use_digits_only",
-        conflicts_with = "use_all_printable_chars",
-        conflicts_with = "base_charset",
-        conflicts_with = "regex_pattern"
+        short = 'n',
+        long = "number",
+        default_value_t = 1,
+        conflicts_with_all = [
+            "pass_name",
+            "export_format",
+            "accounts_file",
+            "interactive",
+            "clip",
+            "qr_file",
+            "wifi_ssid",
+        ]
     )]
-    use_uppercase_and_digits_only: bool,
+    password_quantity: usize,
 
-    /// Use lowercase letters and digits only
+    /// Never generate the same password twice in a batch
+    #[arg(long, conflicts_with_all = ["secrets", "accounts_file"])]
+    unique: bool,
+
+    /// Customize the output format of the password
+    #[arg(short, long = "format")]
+    format_string: Option<String>,
+
+    /// Do not print the trailing newline character
+    #[arg(short = 'N', long)]
+    no_newline: bool,
+
+    /// Use a custom delimiter
+    #[arg(short = 'D', long)]
+    delimiter: Option<String>,
+
+    /// Do not warn about weak passwords
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Always output the strength of the password
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Terminate if the password is weak
+    #[arg(short = 'F', long)]
+    fail: bool,
+
+    /// Output a Kubernetes Secret manifest with the given name
+    #[arg(long = "k8s-secret", value_name = "SECRET_NAME")]
+    k8s_secret: Option<String>,
+
+    /// Namespace of the Kubernetes Secret
+    #[arg(long, requires = "k8s_secret")]
+    namespace: Option<String>,
+
+    /// Write Docker secret files into a directory
+    #[arg(
+        long = "docker-secrets",
+        value_name = "DIRECTORY",
+        conflicts_with = "encryption"
+    )]
+    docker_secrets: Option<PathBuf>,
+
+    /// Add a named secret key, optionally with its own length and preset
+    #[arg(long = "key", value_name = "NAME[:LENGTH[:PRESET]]")]
+    keys: Vec<String>,
+
+    /// Insert the password into the password store under the given name
+    #[arg(
+        long = "pass-insert",
+        value_name = "PASS_NAME",
+        conflicts_with = "secrets"
+    )]
+    pass_name: Option<String>,
+
+    /// Add a metadata line to the password store entry
+    #[arg(long = "meta", value_name = "KEY:VALUE", requires = "pass_name")]
+    metadata: Vec<String>,
+
+    /// Overwrite an existing password store entry
+    #[arg(long, requires = "pass_name")]
+    force: bool,
+
+    /// Export the passwords in a password manager import format
+    #[arg(
+        long = "export",
+        value_name = "FORMAT",
+        conflicts_with_all = ["secrets", "pass_name"]
+    )]
+    export_format: Option<ExportFormat>,
+
+    /// Add an entry name to export or print
+    #[arg(long = "entry", value_name = "NAME", requires = "batch")]
+    entry_names: Vec<String>,
+
+    /// Read entry names to export or print from a file, one per line
+    #[arg(long = "entries", value_name = "FILE", requires = "batch")]
+    entry_file: Option<PathBuf>,
+
+    /// Generate credentials for each account in a CSV file ('-' for stdin)
     #[arg(
-        short = 'L',
-        long = "lowercase",
-        conflicts_with = "use_digits_only",
-        conflicts_with = "use_all_printable_chars",
-        conflicts_with = "base_charset",
-        conflicts_with = "regex_pattern"
+        long = "accounts",
+        value_name = "CSV_FILE",
+        conflicts_with_all = ["secrets", "pass_name", "export_format"]
     )]
+    accounts_file: Option<PathBuf>,
+
+    /// Write the output to a file instead of stdout
+    #[arg(short = 'o', long = "output", value_name = "FILE")]
+    output_file: Option<PathBuf>,
+
+    /// Encrypt the output to an age recipient
+    #[arg(long = "encrypt-to", value_name = "RECIPIENT")]
+    age_recipients: Vec<String>,
+
+    /// Encrypt the output with an age passphrase
+    #[arg(long = "encrypt-passphrase")]
+    encrypt_with_passphrase: bool,
+
+    /// ASCII-armor the encrypted output
+    #[arg(long, requires = "encryption")]
+    armor: bool,
+
+    /// Choose the password in an interactive interface
+    #[arg(
+        short = 'i',
+        long,
+        conflicts_with_all = ["destination", "base_charset", "regex_pattern"]
+    )]
+    interactive: bool,
+
+    /// Copy the password to the clipboard instead of printing it
+    #[arg(
+        short = 'c',
+        long,
+        conflicts_with_all = ["destination", "output_file"]
+    )]
+    clip: bool,
+
+    /// Show the password on the alternate screen for this many seconds
+    #[arg(
+        long = "show-for",
+        value_name = "SECONDS",
+        conflicts_with_all = ["destination", "output_file", "encryption", "interactive", "clip"]
+    )]
+    show_for: Option<u64>,
+
+    /// Color characters by class when printing to a terminal
+    #[arg(short = 'C', long)]
+    colorize: bool,
+
+    /// Print a position ruler under each password on a terminal
+    #[arg(long)]
+    ruler: bool,
+
+    /// Print the NATO phonetic spelling under each password
+    #[arg(long)]
+    phonetic: bool,
+
+    /// Read symbol names for the phonetic spelling from a file
+    #[arg(long = "symbol-names", value_name = "FILE", requires = "spelling")]
+    symbol_names_file: Option<PathBuf>,
+
+    /// Render each password as a QR code in the terminal
+    #[arg(long)]
+    qr: bool,
+
+    /// Write the password as a QR code to an SVG or PNG file
+    #[arg(long = "qr-output", value_name = "FILE", conflicts_with = "encryption")]
+    qr_file: Option<PathBuf>,
+
+    /// Generate a Wi-Fi passphrase with a QR code to join the network
+    #[arg(
+        long = "wifi",
+        value_name = "SSID",
+        conflicts_with_all = ["destination", "interactive", "clip"]
+    )]
+    wifi_ssid: Option<String>,
+
+    /// Print the passwords on cards in an HTML or SVG sheet
+    #[arg(
+        long = "sheet",
+        value_name = "FILE",
+        conflicts_with_all = [
+            "destination",
+            "interactive",
+            "clip",
+            "wifi_ssid",
+            "encryption",
+            "output_file",
+        ]
+    )]
+    sheet_file: Option<PathBuf>,
+
+    /// Clear the clipboard after this many seconds (0 to keep it)
+    #[arg(long = "clip-timeout", value_name = "SECONDS", default_value_t = 45)]
+    clip_timeout: u64,
+}
+
+#[derive(clap::Args)]
+#[command(group(
+    clap::ArgGroup::new("charset").args([
+        "use_uppercase_and_digits_only",
+        "use_lowercase_and_digits_only",
+        "use_digits_only",
+        "use_all_printable_chars",
+        "base_charset",
+        "regex_pattern",
+    ])
+))]
+struct CriteriaArgs {
+    /// Length of the password, or a range such as 16..24 to pick it from
+    #[arg(
+        short = 'l',
+        long = "length",
+        default_value = "20",
+        value_parser = parse_arg::<PasswordLength>
+    )]
+    password_length: PasswordLength,
+
+    /// Weight lengths in a range by the number of passwords of each length
+    #[arg(long = "weighted-length")]
+    weighted_length: bool,
+
+    /// Use uppercase letters and digits only
+    #[arg(short = 'u', long = "uppercase")]
+    use_uppercase_and_digits_only: bool,
+
+    /// Use lowercase letters and digits only
+    #[arg(short = 'L', long = "lowercase")]
     use_lowercase_and_digits_only: bool,
 
     /// Use digits only
+    #[arg(short = 'd', long = "digits")]
+    use_digits_only: bool,
+
+    /// Use all letters, digits, and symbols
+    #[arg(short = 's', long = "symbols")]
+    use_all_printable_chars: bool,
+
+    /// Custom base character set to use
+    #[arg(short, long = "base")]
+    base_charset: Option<String>,
+
+    /// Regex pattern for allowed characters
+    #[arg(short, long = "regex", default_value = "[A-Za-z0-9]")]
+    regex_pattern: Option<String>,
+
+    /// Extra characters to include, or COUNT:CHARS for at least COUNT of CHARS
     #[arg(
-        short = 'd',
-        long = "digits",
-        conflicts_with = "use_all_printable_chars",
-        conflicts_with = "base_charset",
-        conflicts_with = "regex_pattern"
+        short,
+        long = "extra",
+        value_parser = parse_arg::<ExtraChars>,
+        allow_hyphen_values = true
     )]
-    use_digits_only: bool,
+    extra_charset: Vec<ExtraChars>,
+
+    /// Include at least COUNT characters of CHARS, such as 2:!@#
+    #[arg(
+        long = "require",
+        value_name = "COUNT:CHARS",
+        value_parser = parse_arg::<RequiredChars>,
+        allow_hyphen_values = true
+    )]
+    required: Vec<RequiredChars>,
+
+    /// Do not put a character right after the same one
+    #[arg(long)]
+    no_repeat: bool,
+
+    /// Allow at most N consecutive characters of one class
+    #[arg(long, value_name = "N")]
+    max_run: Option<usize>,
+
+    /// Do not put three ascending characters in a row, such as 'abc' or '123'
+    #[arg(long)]
+    no_sequences: bool,
+
+    /// Class of the first character: upper, lower, letter, digit, alnum, or symbol
+    #[arg(long, value_name = "CLASS", value_parser = parse_arg::<CharClass>)]
+    first: Option<CharClass>,
+
+    /// Class of the last character: upper, lower, letter, digit, alnum, or symbol
+    #[arg(long, value_name = "CLASS", value_parser = parse_arg::<CharClass>)]
+    last: Option<CharClass>,
+
+    /// Forbid characters at a position: first, last, N, or -N from the end
+    #[arg(
+        long = "forbid",
+        value_name = "POSITION:CHARS",
+        value_parser = parse_arg::<ForbiddenChars>,
+        allow_hyphen_values = true
+    )]
+    forbidden: Vec<ForbiddenChars>,
+}
+
+#[derive(clap::Args)]
+struct ThresholdArgs {
+    /// Minimum entropy in bits, or 'online', 'offline', 'master', or 'crypto-key'
+    #[arg(
+        long = "min-entropy",
+        value_name = "BITS|LEVEL",
+        default_value = "offline",
+        value_parser = parse_min_entropy
+    )]
+    bits: f64,
+}
+
+/// Parses an option value with the `FromStr` implementation of its type.
+fn parse_arg<T: FromStr<Err = Error>>(s: &str) -> Result<T, String> {
+    s.parse::<T>().map_err(|e| e.to_string())
+}
+
+/// Parses a minimum entropy in bits or the name of a security level.
+fn parse_min_entropy(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(bits) if bits.is_finite() && bits >= 0.0 => Ok(bits),
+        _ => s
+            .parse::<SecurityLevel>()
+            .map(|level| level.bits())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+#[derive(clap::Args)]
+struct GuessRateArgs {
+    /// Set the guess rate of an attacker model for crack times
+    #[arg(
+        long = "guess-rate",
+        value_name = "MODEL=RATE",
+        value_parser = parse_arg::<AttackerModel>
+    )]
+    guess_rates: Vec<AttackerModel>,
+}
+
+impl GuessRateArgs {
+    /// Returns the built-in attacker models with the given rates applied.
+    fn attacker_models(&self) -> Vec<AttackerModel> {
+        AttackerModel::defaults_with(&self.guess_rates)
+    }
+}
+
+#[derive(clap::Args)]
+struct PhraseArgs {
+    /// Number of words in the passphrase
+    #[arg(short = 'w', long = "words", default_value_t = 7)]
+    word_count: usize,
+
+    /// Separator between the words
+    #[arg(short = 'S', long, default_value = " ")]
+    separator: String,
+
+    /// Read the words from a file, one per line, instead of the BIP-39 list
+    #[arg(long = "wordlist", value_name = "FILE")]
+    word_list_file: Option<PathBuf>,
+
+    /// Number of passphrases to generate
+    #[arg(short = 'n', long = "number", default_value_t = 1)]
+    passphrase_quantity: usize,
+
+    #[command(flatten)]
+    min_entropy: ThresholdArgs,
+
+    /// Do not warn about weak passphrases
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(clap::Args)]
+struct PinArgs {
+    /// Number of digits in the PIN
+    #[arg(short = 'l', long = "length", default_value_t = 6)]
+    pin_length: usize,
+
+    /// Number of PINs to generate
+    #[arg(short = 'n', long = "number", default_value_t = 1)]
+    pin_quantity: usize,
+}
+
+#[derive(clap::Args)]
+struct TokenArgs {
+    /// Number of random bytes in the token
+    #[arg(short = 'b', long = "bytes", default_value_t = 32)]
+    byte_count: usize,
+
+    /// Encoding of the token
+    #[arg(short = 'E', long, value_enum, default_value_t = TokenEncoding::Base64url)]
+    encoding: TokenEncoding,
+
+    /// Number of tokens to generate
+    #[arg(short = 'n', long = "number", default_value_t = 1)]
+    token_quantity: usize,
+
+    #[command(flatten)]
+    min_entropy: ThresholdArgs,
+
+    /// Do not warn about weak tokens
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(clap::Args)]
+struct EntropyArgs {
+    #[command(flatten)]
+    criteria: CriteriaArgs,
+
+    #[command(flatten)]
+    min_entropy: ThresholdArgs,
+
+    #[command(flatten)]
+    guess_rates: GuessRateArgs,
+
+    /// Print the explanation as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(clap::Args)]
+struct CheckArgs {
+    #[command(flatten)]
+    min_entropy: ThresholdArgs,
+
+    /// Terminate if a password is weak
+    #[arg(short = 'F', long)]
+    fail: bool,
+}
+
+#[derive(clap::Args)]
+struct PolicyArgs {
+    #[command(flatten)]
+    criteria: CriteriaArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate passwords (the default without a command)
+    Generate(Box<Args>),
+
+    /// Generate a passphrase of random words
+    Phrase(PhraseArgs),
+
+    /// Generate a numeric PIN
+    Pin(PinArgs),
+
+    /// Generate a random token for API keys and session secrets
+    Token(TokenArgs),
+
+    /// Calculate the entropy of the criteria without generating a password
+    Entropy(EntropyArgs),
+
+    /// Estimate the strength of existing passwords read from stdin
+    Check(CheckArgs),
+
+    /// Check passwords read from stdin against the criteria
+    ///
+    /// Each password must be at least as long as '--length', use only
+    /// characters of the charset, and contain every '--extra' character.
+    Policy(PolicyArgs),
+
+    /// Print a shell completion script
+    Completions {
+        /// Shell to complete for
+        shell: Shell,
+    },
+
+    /// Print the man page in roff format
+    Man,
+
+    /// Inspect the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective settings and where they come from
+    Show,
+}
+
+impl CriteriaArgs {
+    fn criteria(&self) -> PasswordCriteria<'_> {
+        if self.use_uppercase_and_digits_only {
+            PasswordCriteria::UppercaseAndDigitsOnly
+        } else if self.use_lowercase_and_digits_only {
+            PasswordCriteria::LowercaseAndDigitsOnly
+        } else if self.use_digits_only {
+            PasswordCriteria::DigitsOnly
+        } else if self.use_all_printable_chars {
+            PasswordCriteria::AllPrintableChars
+        } else if let Some(b) = self.base_charset.as_deref().filter(|b| !b.is_empty()) {
+            PasswordCriteria::BaseCharset(b.as_bytes())
+        } else if let Some(r) = self.regex_pattern.as_deref().filter(|r| !r.is_empty()) {
+            PasswordCriteria::RegexPattern(r)
+        } else {
+            PasswordCriteria::Alphanumeric
+        }
+    }
+
+    fn extra_charset(&self) -> Vec<u8> {
+        self.extra_charset
+            .iter()
+            .flat_map(|extra| match extra {
+                ExtraChars::Literal(chars) => chars.as_slice(),
+                ExtraChars::Required(_) => &[],
+            })
+            .copied()
+            .collect()
+    }
+
+    fn constraints(&self) -> Constraints {
+        Constraints {
+            no_repeat: self.no_repeat,
+            no_sequences: self.no_sequences,
+            max_run: self.max_run,
+            first: self.first,
+            last: self.last,
+            forbidden: self.forbidden.clone(),
+            required: self
+                .extra_charset
+                .iter()
+                .filter_map(|extra| match extra {
+                    ExtraChars::Required(required) => Some(required.clone()),
+                    ExtraChars::Literal(_) => None,
+                })
+                .chain(self.required.iter().cloned())
+                .collect(),
+        }
+    }
+
+    fn password_length(&self) -> PasswordLength {
+        if self.weighted_length {
+            self.password_length
+                .with_distribution(LengthDistribution::Weighted)
+        } else {
+            self.password_length
+        }
+    }
+}
+
+impl Args {
+    fn encrypts_output(&self) -> bool {
+        self.encrypt_with_passphrase || !self.age_recipients.is_empty()
+    }
+
+    fn prints_to_terminal(&self) -> bool {
+        self.output_file.is_none() && !self.encrypts_output() && io::stdout().is_terminal()
+    }
+
+    fn uses_color(&self) -> bool {
+        self.colorize
+            && self.prints_to_terminal()
+            && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+    }
+}
+
+/// Suggests the minimum length whose passwords reach `threshold` and
+/// follow `constraints`.
+fn suggest_length(
+    criteria: &PasswordCriteria,
+    extra_charset: &[u8],
+    constraints: &Constraints,
+    threshold: f64,
+) -> Result<usize, Error> {
+    let unconstrained = suggest_password_length(criteria, Some(extra_charset), threshold)?;
+
+    if constraints.is_empty() {
+        return Ok(unconstrained);
+    }
+
+    let meets_threshold = |length| match calculate_constrained_entropy(
+        length,
+        criteria,
+        Some(extra_charset),
+        constraints,
+    ) {
+        Ok(entropy) => Ok(entropy >= threshold),
+        Err(Error::ConstraintsUnsatisfiable | Error::TooManyExtraChars) => Ok(false),
+        Err(e) => Err(e),
+    };
+    let mut length = unconstrained;
+
+    // The required sets add their characters to the charset, so a shorter
+    // password may be enough.
+    while length > 1 && meets_threshold(length - 1)? {
+        length -= 1;
+    }
+
+    // Rules that leave less than a bit per character out of eight are not
+    // worth a password this long.
+    while !meets_threshold(length)? {
+        if length >= 8 * unconstrained {
+            return Err(Error::UnreachableEntropy(
+                "the constraints leave too few passwords".to_string(),
+            ));
+        }
+
+        length += 1;
+    }
+
+    Ok(length)
+}
+
+fn report_entropy(
+    args: &Args,
+    criteria: &PasswordCriteria,
+    extra_charset: &[u8],
+    password_length: &PasswordLength,
+) -> Result<(), Error> {
+    let constraints = args.criteria.constraints();
+    let entropy =
+        calculate_length_entropy(password_length, criteria, Some(extra_charset), &constraints)?;
+    let threshold = args.min_entropy.bits;
+
+    if entropy >= threshold && args.verbose {
+        print_info(&format!("your password has {:.2} bits of entropy", entropy));
+    }
+
+    if args.verbose {
+        for model in args.guess_rates.attacker_models() {
+            print_info(&format!(
+                "{} ({}) needs {} on average",
+                model.name,
+                format_guess_rate(model.guesses_per_second),
+                format_duration(model.crack_time(entropy))
+            ));
+        }
+    }
+
+    if entropy < threshold && !args.quiet {
+        if args.fail {
+            return Err(Error::PasswordEntropyInsufficient(entropy));
+        } else {
+            print_warning(&format!(
+                "your password has only {:.2} bits of entropy",
+                entropy
+            ));
+        }
+
+        match suggest_length(criteria, extra_charset, &constraints, threshold) {
+            Ok(suggested_length) => print_hint(&format!(
+                "set '--length' to '{}' or longer (use '--quiet' to hide this message)",
+                suggested_length
+            )),
+            Err(e) => print_hint(&e.to_string()),
+        }
+    }
+
+    Ok(())
+}
+
+fn create_secret_entries(
+    args: &Args,
+    criteria: &PasswordCriteria,
+    extra_charset: &[u8],
+) -> Result<Vec<(String, String)>, Error> {
+    let keys = if args.keys.is_empty() {
+        vec![DEFAULT_KEY_NAME.parse::<SecretKey>()?]
+    } else {
+        args.keys
+            .iter()
+            .map(|k| k.parse::<SecretKey>())
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let min_length = args.criteria.constraints().min_length(extra_charset);
+    let mut entries: Vec<(String, String)> = Vec::with_capacity(keys.len());
+
+    for key in keys {
+        if entries.iter().any(|(name, _)| *name == key.name) {
+            return Err(Error::InvalidKeySpec(key.name));
+        }
+
+        let criteria = key.criteria.as_ref().unwrap_or(criteria);
+        let password_length = match key.password_length {
+            Some(password_length) => PasswordLength::Fixed(password_length),
+            None => args.criteria.password_length(),
+        };
+
+        if min_length > password_length.max() {
+            return Err(Error::TooManyExtraChars);
+        }
+
+        if !args.quiet || args.fail {
+            report_entropy(args, criteria, extra_charset, &password_length)?;
+        }
+
+        let password = create_password_with_length(
+            &password_length,
+            criteria,
+            Some(extra_charset),
+            &args.criteria.constraints(),
+        )?;
+
+        entries.push((key.name, password));
+    }
+
+    Ok(entries)
+}
+
+fn create_account_credentials(
+    args: &Args,
+    accounts_file: &Path,
+    criteria: &PasswordCriteria,
+    extra_charset: &[u8],
+) -> Result<(), Error> {
+    let reader: Box<dyn Read> = if accounts_file == Path::new("-") {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(File::open(accounts_file)?)
+    };
+    let min_length = args.criteria.constraints().min_length(extra_charset);
+    let mut reported: Vec<(PasswordLength, PasswordCriteria)> = Vec::new();
+
+    if !args.quiet && !args.encrypts_output() {
+        print_warning("the output contains cleartext passwords; delete it after use");
+    }
+
+    with_output(args, |writer| {
+        generate_account_credentials(reader, writer, |password_length, row_criteria| {
+            let criteria = row_criteria.as_ref().unwrap_or(criteria);
+            let password_length = match password_length {
+                Some(password_length) => PasswordLength::Fixed(password_length),
+                None => args.criteria.password_length(),
+            };
+
+            if min_length > password_length.max() {
+                return Err(Error::TooManyExtraChars);
+            }
+
+            if (!args.quiet || args.fail)
+                && !reported.contains(&(password_length, criteria.clone()))
+            {
+                report_entropy(args, criteria, extra_charset, &password_length)?;
+                reported.push((password_length, criteria.clone()));
+            }
+
+            create_password_with_length(
+                &password_length,
+                criteria,
+                Some(extra_charset),
+                &args.criteria.constraints(),
+            )
+        })
+    })
+}
+
+fn read_passphrase() -> Result<SecretString, Error> {
+    let passphrase = match env::var("RANDPASS_PASSPHRASE") {
+        Ok(p) => p,
+        Err(_) => {
+            let passphrase = rpassword::prompt_password("Enter passphrase: ")?;
+
+            if rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+                return Err(Error::PassphraseMismatch);
+            }
+
+            passphrase
+        }
+    };
+
+    if passphrase.is_empty() {
+        return Err(Error::EmptyPassphrase);
+    }
+
+    Ok(SecretString::from(passphrase))
+}
+
+fn create_encryption(args: &Args) -> Result<Option<Encryption>, Error> {
+    if args.encrypt_with_passphrase {
+        Ok(Some(Encryption::with_passphrase(read_passphrase()?)))
+    } else if !args.age_recipients.is_empty() {
+        Ok(Some(Encryption::to_recipients(&args.age_recipients)?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn with_output<F>(args: &Args, write: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut dyn Write) -> Result<(), Error>,
+{
+    // Ask for the passphrase before truncating the output file.
+    let encryption = create_encryption(args)?;
+    let mut output: Box<dyn Write> = match args.output_file {
+        Some(ref path) => Box::new(create_private_file(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+
+    match encryption {
+        Some(encryption) => {
+            // Never print binary ciphertext to a terminal.
+            let armor = args.armor || (args.output_file.is_none() && io::stdout().is_terminal());
+
+            encryption.encrypt(&mut output, armor, write)?;
+        }
+        None => write(&mut output)?,
+    }
+
+    output.flush()?;
+
+    Ok(())
+}
+
+fn write_output(args: &Args, contents: &str) -> Result<(), Error> {
+    with_output(args, |output| Ok(output.write_all(contents.as_bytes())?))
+}
+
+fn collect_entry_names(args: &Args) -> Result<Vec<String>, Error> {
+    let mut entry_names = args.entry_names.clone();
+
+    if let Some(ref entry_file) = args.entry_file {
+        entry_names.extend(read_entry_names(entry_file)?);
+    }
+
+    Ok(entry_names)
+}
+
+fn copy_password(args: &Args, password: &str) -> Result<(), Error> {
+    copy_to_clipboard(password)?;
+
+    if args.clip_timeout > 0 {
+        if !args.quiet {
+            print_info(&format!(
+                "copied to the clipboard; clearing in {} seconds (press any key to clear now)",
+                args.clip_timeout
+            ));
+        }
+
+        clear_clipboard_after(password, Duration::from_secs(args.clip_timeout))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the ids of the options that other commands share with
+/// `generate`.
+///
+/// These keep the variables and configuration settings of `generate` in
+/// every command.
+fn shared_options() -> Vec<String> {
+    let command = <CriteriaArgs as clap::Args>::augment_args(clap::Command::new("shared"));
+    let command = <ThresholdArgs as clap::Args>::augment_args(command);
+    let command = <GuessRateArgs as clap::Args>::augment_args(command);
+
+    command
+        .get_arguments()
+        .map(|a| a.get_id().to_string())
+        .collect()
+}
+
+/// Returns the name of the environment variable for an option, prefixed
+/// with the name of its command if given.
+fn env_var_name(command: Option<&str>, long: &str) -> String {
+    let name = match command {
+        Some(command) => format!("{}-{}", command, long),
+        None => long.to_string(),
+    };
+
+    format!("RANDPASS_{}", name.to_uppercase().replace('-', "_"))
+}
+
+/// Returns the `RANDPASS_*` environment variable of every option of
+/// `command`, prefixed with `scope` unless the option is shared with
+/// `generate`.
+fn env_var_names<'a>(
+    command: &'a clap::Command,
+    scope: Option<&str>,
+) -> Vec<(&'a clap::Arg, String)> {
+    let shared = shared_options();
+
+    command
+        .get_arguments()
+        .filter_map(|arg| match arg.get_long() {
+            Some("help" | "version") | None => None,
+            Some(long) if shared.contains(&arg.get_id().to_string()) => {
+                Some((arg, env_var_name(None, long)))
+            }
+            Some(long) => Some((arg, env_var_name(scope, long))),
+        })
+        .collect()
+}
+
+/// Lists the environment variable of each option in its help.
+fn with_env_help(command: clap::Command, scope: Option<&str>) -> clap::Command {
+    let names: Vec<(String, String)> = env_var_names(&command, scope)
+        .into_iter()
+        .map(|(arg, name)| (arg.get_id().to_string(), name))
+        .collect();
+
+    command.mut_args(
+        |arg| match names.iter().find(|(id, _)| arg.get_id() == id) {
+            Some((_, name)) => {
+                let help = match arg.get_help() {
+                    Some(help) => format!("{} [env: {}]", help, name),
+                    None => format!("[env: {}]", name),
+                };
+
+                arg.help(help)
+            }
+            None => arg,
+        },
+    )
+}
+
+/// Reads the settings for the options of `command` from their environment
+/// variables, looked up with `var`, keyed by long option name.
+///
+/// Empty variables are ignored, and so is the profile, which selects the
+/// other settings.
+fn env_settings(
+    command: &clap::Command,
+    scope: Option<&str>,
+    var: impl Fn(&str) -> Option<OsString>,
+) -> Result<Table, clap::Error> {
+    let mut settings = Table::new();
+
+    for (arg, name) in env_var_names(command, scope) {
+        let (Some(long), Some(value)) = (arg.get_long(), var(&name)) else {
+            continue;
+        };
+
+        if long == "profile" || value.is_empty() {
+            continue;
+        }
+
+        let value = match arg.get_action() {
+            ArgAction::SetTrue => {
+                match BoolishValueParser::new().parse_ref(command, None, &value) {
+                    Ok(value) => toml::Value::Boolean(value),
+                    Err(_) => {
+                        return Err(clap::Error::raw(
+                            ErrorKind::InvalidValue,
+                            format!(
+                                "invalid value '{}' for '{}': expected a boolean such as 1 or 0\n",
+                                value.to_string_lossy(),
+                                name
+                            ),
+                        ))
+                    }
+                }
+            }
+            _ => match value.into_string() {
+                Ok(value) => toml::Value::String(value),
+                Err(_) => {
+                    return Err(clap::Error::raw(
+                        ErrorKind::InvalidUtf8,
+                        format!("invalid UTF-8 in '{}'\n", name),
+                    ))
+                }
+            },
+        };
+
+        settings.insert(long.to_string(), value);
+    }
+
+    Ok(settings)
+}
+
+/// Commands whose options have their own environment variables and
+/// configuration settings.
+const CONFIGURABLE_COMMANDS: [&str; 6] = ["phrase", "pin", "token", "entropy", "check", "policy"];
+
+/// Builds the command, listing the environment variables for the options
+/// of `generate`, which also apply without a command, and of every other
+/// command.
+fn cli() -> clap::Command {
+    let command =
+        with_env_help(Cli::command(), None).mut_subcommand("generate", |c| with_env_help(c, None));
+
+    CONFIGURABLE_COMMANDS.iter().fold(command, |command, name| {
+        command.mut_subcommand(name, |c| with_env_help(c, Some(name)))
+    })
+}
+
+/// Builds the command that parses the resolved settings alone, without the
+/// defaults and requirements that only apply to the whole command line.
+///
+/// Flags take no value but, unlike with [`ArgAction::SetTrue`], are left
+/// unset when absent, so that they keep the values of the command line.
+fn settings_command(command: clap::Command) -> clap::Command {
+    let names: Vec<String> = command
+        .get_subcommands()
+        .map(|c| c.get_name().to_string())
+        .collect();
+    let command = command.mut_args(|arg| {
+        let arg = arg
+            .default_value(None)
+            .required(false)
+            .requires(Resettable::Reset);
+
+        match arg.get_action() {
+            ArgAction::SetTrue => arg
+                .action(ArgAction::Set)
+                .num_args(0)
+                .default_missing_value("true")
+                .value_parser(clap::value_parser!(bool)),
+            _ => arg,
+        }
+    });
+
+    names.iter().fold(command, |command, name| {
+        command.mut_subcommand(name, settings_command)
+    })
+}
+
+/// Resolves the environment variables and configuration settings for the
+/// options of `subcommand`, or of `generate` without one, that `matches`,
+/// from the command line, leaves unset.
+///
+/// A command other than `generate` only applies the configured settings of
+/// the options it shares with `generate`.
+///
+/// Returns the arguments to parse with [`settings_command`] and the source
+/// of each setting, keyed by argument id.
+fn layered_settings(
+    command: &clap::Command,
+    matches: &ArgMatches,
+    subcommand: Option<&str>,
+    env: &Table,
+    layers: &[Layer],
+) -> Result<(Vec<OsString>, HashMap<String, String>), Error> {
+    let env = Layer {
+        source: "environment".to_string(),
+        settings: env,
+    };
+    let mut args = vec![OsString::from(command.get_name())];
+    let (settings, sources) = match subcommand {
+        Some(name) => {
+            let subcommand = command.find_subcommand(name).unwrap();
+            let matches = matches.subcommand_matches(name).unwrap();
+            let tables = match name {
+                "generate" => layers
+                    .iter()
+                    .map(|l| (l.source.clone(), l.settings.clone()))
+                    .collect(),
+                _ => shared_settings(command, subcommand, layers, &shared_options())?,
+            };
+            let layers: Vec<Layer> = std::iter::once(env)
+                .chain(tables.iter().map(|(source, settings)| Layer {
+                    source: source.clone(),
+                    settings,
+                }))
+                .collect();
+
+            args.push(OsString::from(name));
+            resolve_settings(subcommand, matches, &layers)?
+        }
+        None => {
+            let layers: Vec<Layer> = std::iter::once(env).chain(layers.iter().cloned()).collect();
+
+            resolve_settings(command, matches, &layers)?
+        }
+    };
+
+    args.extend(settings);
+
+    Ok((args, sources))
+}
+
+fn write_completions(shell: Shell, output: &mut dyn Write) -> Result<(), Error> {
+    let mut command = cli();
+    let name = command.get_name().to_string();
+
+    // Unlike `clap_complete::generate`, this reports a closed pipe instead
+    // of panicking.
+    command.set_bin_name(name);
+    command.build();
+    shell.try_generate(&command, output)?;
+
+    Ok(())
+}
+
+fn write_man_page(output: &mut dyn Write) -> Result<(), Error> {
+    clap_mangen::Man::new(cli()).render(output)?;
+
+    Ok(())
+}
+
+fn get_newline(delimiter: Option<&str>, last_line: bool, no_newline: bool) -> String {
+    match delimiter {
+        Some(delimiter) => {
+            if last_line {
+                "".to_string()
+            } else {
+                parse_escape_sequences(delimiter)
+            }
+        }
+        None => {
+            if last_line && no_newline {
+                "".to_string()
+            } else {
+                "\n".to_string()
+            }
+        }
+    }
+}
+
+fn run_phrase(args: &PhraseArgs) -> Result<(), Error> {
+    let words = match args.word_list_file {
+        Some(ref path) => read_word_list(path)?,
+        None => default_word_list(),
+    };
+    let entropy = calculate_passphrase_entropy(words.len(), args.word_count);
+    let threshold = args.min_entropy.bits;
+
+    if entropy < threshold && !args.quiet {
+        print_warning(&format!(
+            "your passphrase has only {:.2} bits of entropy",
+            entropy
+        ));
+        print_hint(&format!(
+            "set '--words' to '{}' or more (use '--quiet' to hide this message)",
+            (threshold / (words.len() as f64).log2()).ceil()
+        ));
+    }
+
+    for _ in 0..args.passphrase_quantity {
+        println!(
+            "{}",
+            create_passphrase(&words, args.word_count, &args.separator)
+        );
+    }
+
+    Ok(())
+}
+
+fn run_pin(args: &PinArgs) -> Result<(), Error> {
+    for _ in 0..args.pin_quantity {
+        println!(
+            "{}",
+            create_password(args.pin_length, &PasswordCriteria::DigitsOnly, None)?
+        );
+    }
+
+    Ok(())
+}
+
+fn run_token(args: &TokenArgs) -> Result<(), Error> {
+    let entropy = 8.0 * args.byte_count as f64;
+    let threshold = args.min_entropy.bits;
+
+    if entropy < threshold && !args.quiet {
+        print_warning(&format!(
+            "your token has only {:.2} bits of entropy",
+            entropy
+        ));
+        print_hint(&format!(
+            "set '--bytes' to '{}' or more (use '--quiet' to hide this message)",
+            (threshold / 8.0).ceil()
+        ));
+    }
+
+    for _ in 0..args.token_quantity {
+        println!("{}", create_token(args.byte_count, args.encoding));
+    }
+
+    Ok(())
+}
 
-    /// Use all letters, digits, and symbols
-    #[arg(
-        short = 's',
-        long = "symbols",
-        conflicts_with = "base_charset",
-        conflicts_with = "regex_pattern"
-    )]
-    use_all_printable_chars: bool,
+fn run_entropy(args: &EntropyArgs) -> Result<(), Error> {
+    let criteria = args.criteria.criteria();
+    let extra_charset: &[u8] = &args.criteria.extra_charset();
+    let password_length = args.criteria.password_length();
+    let constraints = args.criteria.constraints();
 
-    /// Custom base character set to use
-    #[arg(short, long = "base", conflicts_with = "regex_pattern")]
-    base_charset: Option<String>,
+    if constraints.min_length(extra_charset) > password_length.max() {
+        return Err(Error::TooManyExtraChars);
+    }
 
-    /// Regex pattern for allowed characters
-    #[arg(short, long = "regex", default_value = "[A-Za-z0-9]")]
-    regex_pattern: Option<String>,
+    let threshold = args.min_entropy.bits;
+    let suggested_length = suggest_length(&criteria, extra_charset, &constraints, threshold);
+    let models = args.guess_rates.attacker_models();
 
-    /// Extra characters to include
-    #[arg(short, long = "extra")]
-    extra_charset: Option<String>,
+    if let (PasswordLength::Fixed(password_length), true) =
+        (password_length, constraints.is_empty())
+    {
+        let breakdown = explain_entropy(password_length, &criteria, Some(extra_charset))?;
 
-    /// Number of passwords to generate
-    #[arg(short = 'n', long = "number", default_value_t = 1)]
-    password_quantity: usize,
+        if args.json {
+            println!(
+                "{}",
+                render_entropy_json(&breakdown, threshold, &suggested_length, &models)
+            );
+        } else {
+            print!(
+                "{}",
+                render_entropy_report(&breakdown, threshold, &suggested_length, &models)
+            );
+        }
 
-    /// Customize the output format of the password
-    #[arg(short, long = "format")]
-    format_string: Option<String>,
+        return Ok(());
+    }
 
-    /// Do not print the trailing newline character
-    #[arg(short = 'N', long)]
-    no_newline: bool,
+    let length_entropy = LengthEntropy {
+        password_length,
+        constraints: &constraints,
+        charset: {
+            let mut charset =
+                explain_entropy(password_length.max(), &criteria, Some(extra_charset))?.charset;
 
-    /// Use a custom delimiter
-    #[arg(short = 'D', long)]
-    delimiter: Option<String>,
+            charset.extend(constraints.required.iter().flat_map(|r| r.chars.clone()));
+            charset.sort_unstable();
+            charset.dedup();
+            charset
+        },
+        entropies: calculate_entropies_by_length(
+            &password_length,
+            &criteria,
+            Some(extra_charset),
+            &constraints,
+        )?,
+        total: calculate_length_entropy(
+            &password_length,
+            &criteria,
+            Some(extra_charset),
+            &constraints,
+        )?,
+    };
 
-    /// Do not warn about weak passwords
-    #[arg(short, long, conflicts_with = "verbose")]
-    quiet: bool,
+    if args.json {
+        println!(
+            "{}",
+            render_length_entropy_json(&length_entropy, threshold, &suggested_length, &models)
+        );
+    } else {
+        print!(
+            "{}",
+            render_length_entropy_report(&length_entropy, threshold, &suggested_length, &models)
+        );
+    }
 
-    /// Always output the strength of the password
-    #[arg(short, long)]
-    verbose: bool,
+    Ok(())
+}
 
-    /// Terminate if the password is weak
-    #[arg(short = 'F', long)]
-    fail: bool,
+/// Reads passwords from stdin, one per line, or prompts for a single one on
+/// a terminal.
+fn read_passwords() -> Result<Vec<String>, Error> {
+    if io::stdin().is_terminal() {
+        Ok(vec![rpassword::prompt_password("Password: ")?])
+    } else {
+        Ok(io::stdin().lines().collect::<Result<_, _>>()?)
+    }
 }
 
-fn report_entropy(
-    criteria: &PasswordCriteria,
-    extra_charset: &[u8],
-    password_length: usize,
-    verbose: bool,
-    quiet: bool,
-    fail: bool,
-) -> Result<(), Error> {
-    let entropy = calculate_entropy(password_length, criteria, Some(extra_charset))?;
+fn run_check(args: &CheckArgs) -> Result<(), Error> {
+    let threshold = args.min_entropy.bits;
+    let mut weakest = f64::INFINITY;
 
-    if entropy >= ENTROPY_THRESHOLD && verbose {
-        print_info(&format!("your password has {:.2} bits of entropy", entropy));
+    for password in read_passwords()? {
+        let entropy = estimate_entropy(&password);
+        let rating = if entropy < threshold {
+            "weak"
+        } else {
+            "strong"
+        };
+
+        println!("{:.2} bits ({})", entropy, rating);
+        weakest = weakest.min(entropy);
     }
 
-    if entropy < ENTROPY_THRESHOLD && !quiet {
-        if fail {
-            return Err(Error::PasswordEntropyInsufficient(entropy));
+    if args.fail && weakest < threshold {
+        return Err(Error::PasswordEntropyInsufficient(weakest));
+    }
+
+    Ok(())
+}
+
+fn run_policy(args: &PolicyArgs) -> Result<(), Error> {
+    let criteria = args.criteria.criteria();
+    let extra_charset: &[u8] = &args.criteria.extra_charset();
+    let mut violating = 0;
+
+    for password in read_passwords()? {
+        let violations = check_policy(
+            &password,
+            args.criteria.password_length().min(),
+            &criteria,
+            extra_charset,
+            &args.criteria.constraints(),
+        )?;
+
+        if violations.is_empty() {
+            println!("ok");
         } else {
-            print_warning(&format!(
-                "your password has only {:.2} bits of entropy",
-                entropy
-            ));
+            println!(
+                "violated: {}",
+                violations
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            );
+            violating += 1;
         }
+    }
 
-        if let Some(suggested_length) = suggest_password_length(criteria, Some(extra_charset)) {
-            print_hint(&format!(
-                "set '--length' to '{}' or longer (use '--quiet' to hide this message)",
-                suggested_length
-            ));
-        }
+    if violating > 0 {
+        return Err(Error::PolicyViolated(violating));
     }
 
     Ok(())
 }
 
-fn get_newline(delimiter: Option<&str>, last_line: bool, no_newline: bool) -> String {
-    match delimiter {
-        Some(delimiter) => {
-            if last_line {
-                "".to_string()
-            } else {
-                parse_escape_sequences(delimiter)
-            }
+fn run() -> Result<(), Error> {
+    let command = cli();
+    let matches = command.clone().get_matches();
+    let mut cli_args = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // The options of `generate` also apply without a command, but not
+    // before one. Only the global options may precede a command.
+    if let Some((name, _)) = matches.subcommand() {
+        if let Some(arg) = command.get_arguments().find(|a| {
+            !a.is_global_set()
+                && matches.value_source(a.get_id().as_str()) == Some(ValueSource::CommandLine)
+        }) {
+            command
+                .clone()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!(
+                        "the subcommand '{}' cannot be used with '--{}'",
+                        name,
+                        arg.get_long().unwrap_or_default()
+                    ),
+                )
+                .exit();
         }
-        None => {
-            if last_line && no_newline {
-                "".to_string()
-            } else {
-                "\n".to_string()
+    }
+
+    match cli_args.command {
+        Some(Command::Pin(_)) if cli_args.profile.is_some() => {
+            command
+                .clone()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "the subcommand 'pin' cannot be used with '--profile'",
+                )
+                .exit();
+        }
+        Some(Command::Completions { shell }) => {
+            return write_completions(shell, &mut io::stdout());
+        }
+        Some(Command::Man) => return write_man_page(&mut io::stdout()),
+        _ => {}
+    }
+
+    // `config show` renders the settings of `generate`.
+    let subcommand = matches.subcommand_name().filter(|name| *name != "config");
+    let scope = subcommand.filter(|name| *name != "generate");
+    let target = subcommand.map_or(&command, |name| command.find_subcommand(name).unwrap());
+    let env = env_settings(target, scope, |name| env::var_os(name)).unwrap_or_else(|e| e.exit());
+    let profile = cli_args
+        .profile
+        .clone()
+        .or_else(|| env::var("RANDPASS_PROFILE").ok().filter(|p| !p.is_empty()));
+    let config = Config::load()?;
+    let (settings, sources) = layered_settings(
+        &command,
+        &matches,
+        subcommand,
+        &env,
+        &config.layers(profile.as_deref())?,
+    )?;
+    // The settings never repeat or conflict with the given options, so they
+    // can be parsed on their own and applied over them.
+    let layered = settings_command(command.clone())
+        .try_get_matches_from(settings)
+        .unwrap_or_else(|e| e.exit());
+
+    match cli_args.command.as_mut() {
+        Some(Command::Config {
+            action: ConfigAction::Show,
+        }) => {
+            if let Some(path) = Config::path() {
+                println!("# {}", path.display());
             }
+            print!(
+                "{}",
+                render_settings(&command, &matches, &layered, &sources)
+            );
+
+            return Ok(());
         }
+        Some(subcommand) => subcommand.update_from_arg_matches(&layered),
+        None => cli_args.generate.update_from_arg_matches(&layered),
+    }
+    .unwrap_or_else(|e| e.exit());
+
+    match cli_args.command {
+        Some(Command::Generate(args)) => generate(*args),
+        Some(Command::Phrase(args)) => run_phrase(&args),
+        Some(Command::Pin(args)) => run_pin(&args),
+        Some(Command::Token(args)) => run_token(&args),
+        Some(Command::Entropy(args)) => run_entropy(&args),
+        Some(Command::Check(args)) => run_check(&args),
+        Some(Command::Policy(args)) => run_policy(&args),
+        Some(Command::Completions { .. } | Command::Man | Command::Config { .. }) => Ok(()),
+        None => generate(cli_args.generate),
     }
 }
 
-fn run() -> Result<(), Error> {
-    let args = Args::parse();
-    let base_charset = match args.base_charset {
-        Some(b) => b.as_bytes().to_vec(),
-        None => vec![],
-    };
-    let extra_charset = match args.extra_charset {
-        Some(e) => e.as_bytes().to_vec(),
-        None => vec![],
-    };
-    let regex_pattern = args.regex_pattern.unwrap();
+/// Creates `count` passwords, all distinct with '--unique'.
+fn create_passwords(
+    args: &Args,
+    count: usize,
+    password_length: &PasswordLength,
+    criteria: &PasswordCriteria,
+    extra_charset: &[u8],
+    constraints: &Constraints,
+) -> Result<Vec<String>, Error> {
+    if args.unique {
+        return create_unique_passwords(
+            count,
+            password_length,
+            criteria,
+            Some(extra_charset),
+            constraints,
+        );
+    }
+
+    create_passwords_with_length(
+        count,
+        password_length,
+        criteria,
+        Some(extra_charset),
+        constraints,
+    )
+}
 
-    if extra_charset.len() > args.password_length {
+fn generate(args: Args) -> Result<(), Error> {
+    let criteria = args.criteria.criteria();
+    let extra_charset: &[u8] = &args.criteria.extra_charset();
+    let password_length = args.criteria.password_length();
+    let constraints = args.criteria.constraints();
+
+    if constraints.min_length(extra_charset) > password_length.max() {
         return Err(Error::TooManyExtraChars);
     }
 
-    let criteria = if args.use_uppercase_and_digits_only {
-        PasswordCriteria::UppercaseAndDigitsOnly
-    } else if args.use_lowercase_and_digits_only {
-        PasswordCriteria::LowercaseAndDigitsOnly
-    } else if args.use_digits_only {
-        PasswordCriteria::DigitsOnly
-    } else if args.use_all_printable_chars {
-        PasswordCriteria::AllPrintableChars
-    } else if !base_charset.is_empty() {
-        PasswordCriteria::BaseCharset(&base_charset)
-    } else if !regex_pattern.is_empty() {
-        PasswordCriteria::RegexPattern(&regex_pattern)
-    } else {
-        PasswordCriteria::Alphanumeric
-    };
+    if args.k8s_secret.is_some() || args.docker_secrets.is_some() {
+        let entries = create_secret_entries(&args, &criteria, extra_charset)?;
+
+        if let Some(ref dir) = args.docker_secrets {
+            write_docker_secrets(dir, &entries)?;
+        }
+
+        if let Some(ref name) = args.k8s_secret {
+            write_output(
+                &args,
+                &render_kubernetes_secret(name, args.namespace.as_deref(), &entries)?,
+            )?;
+        }
+
+        return Ok(());
+    }
+
+    if args.interactive {
+        if let PasswordLength::Range { .. } = password_length {
+            return Err(Error::LengthRangeUnsupported("--interactive".to_string()));
+        }
+
+        return match run_interactive(
+            Selection::new(password_length.min(), &criteria),
+            extra_charset,
+            &constraints,
+            args.min_entropy.bits,
+        )? {
+            Outcome::Print(password) => write_output(&args, &format!("{}\n", password)),
+            Outcome::Copy(password) => copy_password(&args, &password),
+        };
+    }
+
+    if let Some(ref accounts_file) = args.accounts_file {
+        return create_account_credentials(&args, accounts_file, &criteria, extra_charset);
+    }
 
     if !args.quiet || args.fail {
-        report_entropy(
+        report_entropy(&args, &criteria, extra_charset, &password_length)?;
+    }
+
+    if args.clip {
+        let password = create_password_with_length(
+            &password_length,
+            &criteria,
+            Some(extra_charset),
+            &constraints,
+        )?;
+
+        return copy_password(&args, &password);
+    }
+
+    if let Some(ref pass_name) = args.pass_name {
+        let password = create_password_with_length(
+            &password_length,
+            &criteria,
+            Some(extra_charset),
+            &constraints,
+        )?;
+        let path = PasswordStore::open()?.insert(
+            pass_name,
+            &format_pass_entry(&password, &args.metadata)?,
+            args.force,
+        )?;
+
+        if args.verbose {
+            print_info(&format!("saved password to '{}'", path.display()));
+        }
+
+        return Ok(());
+    }
+
+    if let Some(export_format) = args.export_format {
+        let entry_names = collect_entry_names(&args)?;
+
+        if entry_names.is_empty() {
+            return Err(Error::NoEntries);
+        }
+
+        let passwords = create_passwords(
+            &args,
+            entry_names.len(),
+            &password_length,
+            &criteria,
+            extra_charset,
+            &constraints,
+        )?;
+        let entries: Vec<_> = entry_names.into_iter().zip(passwords).collect();
+
+        if !args.quiet && !args.encrypts_output() {
+            print_warning("the export contains cleartext passwords; delete it after importing");
+        }
+
+        write_output(&args, &render_export(export_format, &entries))?;
+
+        return Ok(());
+    }
+
+    let mut output = String::new();
+    let use_color = args.uses_color();
+    let show_ruler = args.ruler && args.prints_to_terminal();
+    let symbol_names = match args.symbol_names_file {
+        Some(ref path) => SymbolNames::from_table(&fs::read_to_string(path)?)?,
+        None => SymbolNames::default(),
+    };
+
+    if let Some(ref sheet_file) = args.sheet_file {
+        let entry_names = collect_entry_names(&args)?;
+        let usernames: Vec<Option<String>> = if entry_names.is_empty() {
+            vec![None; args.password_quantity]
+        } else {
+            entry_names.into_iter().map(Some).collect()
+        };
+        let passwords = create_passwords(
+            &args,
+            usernames.len(),
+            &password_length,
             &criteria,
-            &extra_charset,
-            args.password_length,
-            args.verbose,
-            args.quiet,
-            args.fail,
+            extra_charset,
+            &constraints,
         )?;
+        let cards: Vec<Card> = usernames
+            .into_iter()
+            .zip(passwords)
+            .map(|(username, password)| Card { username, password })
+            .collect();
+
+        write_sheet(sheet_file, &cards, &symbol_names, args.qr)?;
+
+        if !args.quiet {
+            print_warning("the sheet contains cleartext passwords; delete it after printing");
+        }
+
+        return Ok(());
     }
 
-    for i in 0..args.password_quantity {
+    let passwords = create_passwords(
+        &args,
+        args.password_quantity,
+        &password_length,
+        &criteria,
+        extra_charset,
+        &constraints,
+    )?;
+
+    for (i, password) in passwords.into_iter().enumerate() {
         let newline = get_newline(
             args.delimiter.as_deref(),
             i == args.password_quantity - 1,
             args.no_newline,
         );
-        let password = match create_password(args.password_length, &criteria, Some(&extra_charset))
-        {
-            Ok(p) => p,
-            Err(_) => panic!(),
+
+        let rendered = if use_color {
+            colorize_password(&password)
+        } else {
+            password.clone()
         };
 
         match args.format_string {
             Some(ref format_string) => {
-                print!("{}{}", format_string.replace("{}", &password), newline)
+                output.push_str(&format_string.replace("{}", &rendered));
             }
-            None => print!("{}{}", password, newline),
+            None => output.push_str(&rendered),
+        };
+
+        if show_ruler {
+            let offset = args
+                .format_string
+                .as_ref()
+                .and_then(|f| f.find("{}").map(|i| f[..i].chars().count()))
+                .unwrap_or(0);
+
+            output.push('\n');
+            output.push_str(&create_ruler(password.len(), offset, use_color));
+        }
+
+        if args.phonetic {
+            output.push('\n');
+            output.push_str(&spell_phonetically(&password, &symbol_names));
+        }
+
+        let qr_data = match args.wifi_ssid {
+            Some(ref ssid) => wifi_payload(ssid, &password)?,
+            None => password.clone(),
         };
+
+        if args.qr || args.wifi_ssid.is_some() {
+            output.push('\n');
+            output.push_str(&render_qr_terminal(&qr_data)?);
+        }
+
+        if let Some(ref qr_file) = args.qr_file {
+            write_qr_file(&qr_data, qr_file)?;
+        }
+
+        output.push_str(&newline);
     }
 
-    Ok(())
+    match args.show_for {
+        Some(seconds) => show_ephemeral(&output, Duration::from_secs(seconds)),
+        None => write_output(&args, &output),
+    }
 }
 
 fn main() {
@@ -249,4 +1776,282 @@ mod tests {
 
         assert_eq!(get_newline(None, false, false), "\n");
     }
+
+    #[test]
+    fn test_parse_commands() {
+        let cli = Cli::try_parse_from(["randpass", "-l", "8"]).unwrap();
+
+        assert!(cli.command.is_none());
+        assert_eq!(
+            cli.generate.criteria.password_length(),
+            PasswordLength::Fixed(8)
+        );
+
+        let cli = Cli::try_parse_from(["randpass", "generate", "-l", "8"]).unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Some(Command::Generate(ref args))
+                if args.criteria.password_length == PasswordLength::Fixed(8)
+        ));
+
+        let cli = Cli::try_parse_from(["randpass", "-l", "16..24", "--weighted-length"]).unwrap();
+
+        assert_eq!(
+            cli.generate.criteria.password_length(),
+            PasswordLength::Range {
+                min: 16,
+                max: 24,
+                distribution: LengthDistribution::Weighted,
+            }
+        );
+
+        let cli = Cli::try_parse_from(["randpass", "pin", "-l", "4"]).unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Some(Command::Pin(ref args)) if args.pin_length == 4
+        ));
+
+        assert!(Cli::try_parse_from(["randpass", "pin", "-s"]).is_err());
+
+        // Counted sets in '-e' are required like those of '--require', and
+        // a backslash keeps a value that looks like one literal.
+        let cli = Cli::try_parse_from([
+            "randpass",
+            "-e",
+            "2:!@#",
+            "-e",
+            "\\12:30",
+            "-e",
+            "-",
+            "--require",
+            "1:ab",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.generate.criteria.extra_charset(), b"12:30-");
+        assert_eq!(
+            cli.generate.criteria.constraints().required,
+            vec![
+                "2:!@#".parse::<RequiredChars>().unwrap(),
+                "1:ab".parse::<RequiredChars>().unwrap(),
+            ]
+        );
+        assert!(Cli::try_parse_from(["randpass", "-e", "0:!"]).is_err());
+
+        // Positional rules are checked while parsing, naming the option.
+        let error = Cli::try_parse_from(["randpass", "--forbid", "x:1"])
+            .err()
+            .unwrap();
+
+        assert_eq!(error.kind(), clap::error::ErrorKind::ValueValidation);
+        assert!(error.to_string().contains("--forbid"));
+
+        let error = Cli::try_parse_from(["randpass", "entropy", "--guess-rate", "online"])
+            .err()
+            .unwrap();
+
+        assert_eq!(error.kind(), clap::error::ErrorKind::ValueValidation);
+        assert!(error.to_string().contains("--guess-rate"));
+    }
+
+    #[test]
+    fn test_cleartext_files() {
+        // Sheets and QR codes are written in cleartext, so they never go
+        // with encryption.
+        for args in [
+            ["--sheet", "s.html", "--encrypt-to", "age1x"],
+            ["--sheet", "s.html", "--encrypt-passphrase", "-q"],
+            ["--sheet", "s.html", "-o", "out.txt"],
+            ["--qr-output", "qr.png", "--encrypt-to", "age1x"],
+            ["--qr-output", "qr.svg", "--encrypt-passphrase", "-q"],
+        ] {
+            let error = Cli::try_parse_from(["randpass"].into_iter().chain(args))
+                .err()
+                .unwrap();
+
+            assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+        }
+    }
+
+    #[test]
+    fn test_min_entropy() {
+        let bits = parse_min_entropy;
+
+        assert_eq!(bits("offline").unwrap(), 72.0);
+        assert_eq!(bits("crypto-key").unwrap(), 128.0);
+        assert_eq!(bits("50.5").unwrap(), 50.5);
+        assert!(bits("-1").is_err());
+        assert!(bits("inf").is_err());
+        assert!(bits("paranoid").is_err());
+    }
+
+    fn long_flags() -> Vec<String> {
+        Cli::command()
+            .get_arguments()
+            .filter_map(|a| a.get_long())
+            .map(|l| format!("--{}", l))
+            .collect()
+    }
+
+    #[test]
+    fn test_cli_env() {
+        let command = cli();
+
+        for arg in command.get_arguments() {
+            let help = arg.get_help().map(|h| h.to_string()).unwrap_or_default();
+
+            match arg.get_long() {
+                Some("help" | "version") | None => assert!(!help.contains("[env: ")),
+                Some(long) => {
+                    assert!(help.ends_with(&format!("[env: {}]", env_var_name(None, long))))
+                }
+            }
+        }
+
+        assert_eq!(env_var_name(None, "clip-timeout"), "RANDPASS_CLIP_TIMEOUT");
+        assert_eq!(env_var_name(Some("pin"), "length"), "RANDPASS_PIN_LENGTH");
+
+        let vars = HashMap::from([
+            ("RANDPASS_DIGITS", "1"),
+            ("RANDPASS_SYMBOLS", "yes"),
+            ("RANDPASS_QUIET", "off"),
+            ("RANDPASS_LENGTH", "8"),
+            ("RANDPASS_ENTRY", ""),
+            ("RANDPASS_PROFILE", "db"),
+            ("RANDPASS_PIN_LENGTH", "4"),
+            ("RANDPASS_PHRASE_MIN_ENTROPY", "100"),
+            ("RANDPASS_MIN_ENTROPY", "50"),
+        ]);
+        let var = |name: &str| vars.get(name).map(OsString::from);
+        let settings = env_settings(&command, None, var).unwrap();
+
+        assert_eq!(
+            settings,
+            toml::toml! {
+                digits = true
+                symbols = true
+                quiet = false
+                length = "8"
+                min-entropy = "50"
+            }
+        );
+
+        // The options shared with `generate` keep its variables, the others
+        // have their own.
+        let pin = env_settings(command.find_subcommand("pin").unwrap(), Some("pin"), var).unwrap();
+
+        assert_eq!(pin, toml::toml! { length = "4" });
+
+        let phrase = command.find_subcommand("phrase").unwrap();
+
+        assert_eq!(
+            env_settings(phrase, Some("phrase"), var).unwrap(),
+            toml::toml! { min-entropy = "50" }
+        );
+
+        let var = |name: &str| (name == "RANDPASS_DIGITS").then(|| OsString::from("maybe"));
+
+        assert_eq!(
+            env_settings(&command, None, var).unwrap_err().kind(),
+            ErrorKind::InvalidValue
+        );
+
+        // A flag overrides its own variable and those of conflicting
+        // options, and the variables override the configuration.
+        let config = "symbols = true\nlength = 24\nnumber = 2\n"
+            .parse::<Config>()
+            .unwrap();
+        let layers = config.layers(None).unwrap();
+        let matches = command
+            .clone()
+            .get_matches_from(["randpass", "-d", "-l", "12"]);
+        let (args, sources) =
+            layered_settings(&command, &matches, None, &settings, &layers).unwrap();
+
+        assert_eq!(args, ["randpass", "--min-entropy=50", "--number=2"]);
+        assert_eq!(sources["bits"], "environment");
+        assert_eq!(sources["password_quantity"], "config");
+
+        let matches = command.clone().get_matches_from(["randpass", "-q"]);
+        let (args, _) = layered_settings(&command, &matches, None, &settings, &layers).unwrap();
+        let layered = settings_command(command.clone())
+            .try_get_matches_from(args)
+            .unwrap();
+        let mut cli_args = Cli::from_arg_matches(&matches).unwrap();
+
+        cli_args.generate.update_from_arg_matches(&layered).unwrap();
+
+        let args = cli_args.generate;
+
+        // Within a layer, the first of two conflicting settings applies.
+        assert!(args.criteria.use_digits_only);
+        assert!(!args.criteria.use_all_printable_chars);
+        assert!(args.quiet);
+        assert_eq!(args.criteria.password_length, PasswordLength::Fixed(8));
+        assert_eq!(args.password_quantity, 2);
+        assert_eq!(args.clip_timeout, 45);
+
+        // Another command applies its own variables and those it shares
+        // with `generate`.
+        let matches = command.clone().get_matches_from(["randpass", "entropy"]);
+        let entropy = command.find_subcommand("entropy").unwrap();
+        let settings = env_settings(entropy, Some("entropy"), |name: &str| {
+            (name == "RANDPASS_LENGTH").then(|| OsString::from("12"))
+        })
+        .unwrap();
+        let (args, sources) =
+            layered_settings(&command, &matches, Some("entropy"), &settings, &layers).unwrap();
+
+        assert_eq!(args, ["randpass", "entropy", "--length=12", "--symbols"]);
+        assert_eq!(sources["password_length"], "environment");
+    }
+
+    #[test]
+    fn test_write_completions() {
+        for shell in [
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::Elvish,
+            Shell::PowerShell,
+        ] {
+            let mut output = Vec::new();
+
+            write_completions(shell, &mut output).unwrap();
+
+            let script = String::from_utf8(output).unwrap();
+
+            for flag in long_flags() {
+                // Fish lists long options without their dashes.
+                let expected = match shell {
+                    Shell::Fish => format!("-l {}", &flag[2..]),
+                    _ => flag.clone(),
+                };
+
+                assert!(script.contains(&expected), "{:?} lacks {}", shell, flag);
+            }
+
+            assert!(script.contains("completions"));
+        }
+    }
+
+    #[test]
+    fn test_write_man_page() {
+        let mut output = Vec::new();
+
+        write_man_page(&mut output).unwrap();
+
+        let page = String::from_utf8(output).unwrap();
+
+        assert!(page.contains(".TH randpass"));
+        for flag in long_flags() {
+            assert!(
+                page.contains(&flag.replace('-', "\\-")),
+                "man page lacks {}",
+                flag
+            );
+        }
+    }
 }
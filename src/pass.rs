@@ -0,0 +1,250 @@
+use randpass::Error;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// A `pass` password store rooted at a directory.
+pub(crate) struct PasswordStore {
+    root: PathBuf,
+
+    /// GnuPG home directory, or `None` for the default one.
+    gnupg_home: Option<PathBuf>,
+}
+
+impl PasswordStore {
+    /// Opens the store at `$PASSWORD_STORE_DIR`, falling back to
+    /// `~/.password-store`.
+    pub(crate) fn open() -> Result<Self, Error> {
+        let root = match env::var_os("PASSWORD_STORE_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => match env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(".password-store"),
+                None => return Err(Error::PassStoreNotFound),
+            },
+        };
+
+        Ok(PasswordStore {
+            root,
+            gnupg_home: None,
+        })
+    }
+
+    /// Encrypts `contents` for the recipients listed in the nearest
+    /// `.gpg-id` file and writes it to `<root>/<name>.gpg`.
+    ///
+    /// Returns the path of the created file.
+    pub(crate) fn insert(&self, name: &str, contents: &str, force: bool) -> Result<PathBuf, Error> {
+        let relative = Path::new(name);
+
+        if name.is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        {
+            return Err(Error::InvalidPassName(name.to_string()));
+        }
+
+        let path = self.root.join(format!("{}.gpg", name));
+
+        if path.exists() && !force {
+            return Err(Error::PassEntryExists(name.to_string()));
+        }
+
+        let recipients = self.find_recipients(path.parent().unwrap_or(&self.root))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut gpg = Command::new("gpg");
+
+        if let Some(ref gnupg_home) = self.gnupg_home {
+            gpg.env("GNUPGHOME", gnupg_home);
+        }
+
+        gpg.args([
+            "--batch",
+            "--yes",
+            "--quiet",
+            "--compress-algo=none",
+            "--no-encrypt-to",
+            "--encrypt",
+        ]);
+
+        for recipient in &recipients {
+            gpg.args(["--recipient", recipient]);
+        }
+
+        let mut child = gpg
+            .arg("--output")
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(contents.as_bytes())?;
+        }
+
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            return Err(Error::GpgFailed(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        Ok(path)
+    }
+
+    /// Reads the recipients from the `.gpg-id` file closest to `dir`,
+    /// searching upwards until the store root.
+    fn find_recipients(&self, dir: &Path) -> Result<Vec<String>, Error> {
+        for ancestor in dir.ancestors() {
+            if let Ok(ids) = fs::read_to_string(ancestor.join(".gpg-id")) {
+                let recipients: Vec<String> = ids
+                    .lines()
+                    .map(|l| l.split('#').next().unwrap_or_default().trim())
+                    .filter(|l| !l.is_empty())
+                    .map(|l| l.to_string())
+                    .collect();
+
+                if !recipients.is_empty() {
+                    return Ok(recipients);
+                }
+            }
+
+            if ancestor == self.root {
+                break;
+            }
+        }
+
+        Err(Error::GpgIdNotFound)
+    }
+}
+
+/// Builds the contents of a `pass` entry: the password on the first line,
+/// followed by one `key: value` line per metadata item.
+pub(crate) fn format_pass_entry(password: &str, metadata: &[String]) -> Result<String, Error> {
+    let mut contents = format!("{}\n", password);
+
+    for item in metadata {
+        match item.split_once(':') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                contents.push_str(&format!("{}: {}\n", key.trim(), value.trim()));
+            }
+            _ => return Err(Error::InvalidMetadata(item.to_string())),
+        }
+    }
+
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_pass_entry() {
+        assert_eq!(format_pass_entry("hunter2", &[]).unwrap(), "hunter2\n");
+
+        assert_eq!(
+            format_pass_entry(
+                "hunter2",
+                &[
+                    "user:alice".to_string(),
+                    "url: https://example.com".to_string()
+                ]
+            )
+            .unwrap(),
+            "hunter2\nuser: alice\nurl: https://example.com\n"
+        );
+
+        assert!(format_pass_entry("hunter2", &["user".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_insert_invalid_name() {
+        let store = PasswordStore {
+            root: env::temp_dir(),
+            gnupg_home: None,
+        };
+
+        assert!(store.insert("", "hunter2\n", false).is_err());
+        assert!(store.insert("../escape", "hunter2\n", false).is_err());
+        assert!(store.insert("/etc/passwd", "hunter2\n", false).is_err());
+    }
+
+    #[test]
+    fn test_insert_and_decrypt() {
+        if Command::new("gpg").arg("--version").output().is_err() {
+            return;
+        }
+
+        let dir = env::temp_dir().join(format!("randpass-pass-{}", std::process::id()));
+        let gnupg_home = dir.join("gnupg");
+        let root = dir.join("store");
+        let gpg = |args: &[&str]| {
+            Command::new("gpg")
+                .env("GNUPGHOME", &gnupg_home)
+                .args(["--batch", "--quiet"])
+                .args(args)
+                .output()
+                .unwrap()
+        };
+
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&gnupg_home).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&gnupg_home, fs::Permissions::from_mode(0o700)).unwrap();
+        }
+
+        assert!(gpg(&[
+            "--passphrase",
+            "",
+            "--quick-gen-key",
+            "randpass-test@example.invalid",
+            "default",
+            "default",
+            "never",
+        ])
+        .status
+        .success());
+
+        fs::write(root.join(".gpg-id"), "randpass-test@example.invalid\n").unwrap();
+
+        let store = PasswordStore {
+            root: root.clone(),
+            gnupg_home: Some(gnupg_home.clone()),
+        };
+        let path = store
+            .insert("web/example.com", "hunter2\nuser: alice\n", false)
+            .unwrap();
+
+        assert_eq!(path, root.join("web/example.com.gpg"));
+
+        assert!(matches!(
+            store.insert("web/example.com", "hunter3\n", false),
+            Err(Error::PassEntryExists(_))
+        ));
+
+        store.insert("web/example.com", "hunter3\n", true).unwrap();
+
+        let decrypted = gpg(&["--decrypt", path.to_str().unwrap()]);
+
+        assert_eq!(String::from_utf8_lossy(&decrypted.stdout), "hunter3\n");
+
+        let _ = Command::new("gpgconf")
+            .env("GNUPGHOME", &gnupg_home)
+            .args(["--kill", "gpg-agent"])
+            .output();
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use crate::Error;
+
+const LETTER_NAMES: [&str; 26] = [
+    "alfa", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliett",
+    "kilo", "lima", "mike", "november", "oscar", "papa", "quebec", "romeo", "sierra", "tango",
+    "uniform", "victor", "whiskey", "x-ray", "yankee", "zulu",
+];
+
+const DIGIT_NAMES: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+const ENGLISH_SYMBOL_NAMES: [(char, &str); 33] = [
+    (' ', "space"),
+    ('!', "exclamation-mark"),
+    ('"', "double-quote"),
+    ('#', "hash"),
+    ('$', "dollar-sign"),
+    ('%', "percent-sign"),
+    ('&', "ampersand"),
+    ('\'', "apostrophe"),
+    ('(', "left-parenthesis"),
+    (')', "right-parenthesis"),
+    ('*', "asterisk"),
+    ('+', "plus-sign"),
+    (',', "comma"),
+    ('-', "hyphen"),
+    ('.', "period"),
+    ('/', "slash"),
+    (':', "colon"),
+    (';', "semicolon"),
+    ('<', "less-than-sign"),
+    ('=', "equals-sign"),
+    ('>', "greater-than-sign"),
+    ('?', "question-mark"),
+    ('@', "at-sign"),
+    ('[', "left-bracket"),
+    ('\\', "backslash"),
+    (']', "right-bracket"),
+    ('^', "caret"),
+    ('_', "underscore"),
+    ('`', "backtick"),
+    ('{', "left-brace"),
+    ('|', "vertical-bar"),
+    ('}', "right-brace"),
+    ('~', "tilde"),
+];
+
+/// Names for the symbols in a phonetic spelling.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SymbolNames(HashMap<char, String>);
+
+impl Default for SymbolNames {
+    fn default() -> Self {
+        SymbolNames(
+            ENGLISH_SYMBOL_NAMES
+                .iter()
+                .map(|&(c, name)| (c, name.to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl SymbolNames {
+    /// Overrides symbol names from a table with one `<symbol> <name>` entry
+    /// per line, such as `@ klammeraffe`.
+    ///
+    /// # Parameters
+    ///
+    /// - `table`: Symbol-name table.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(SymbolNames)` with the English names overridden by the table on
+    /// success; `Err(Error)` if a line is malformed.
+    pub fn from_table(table: &str) -> Result<Self, Error> {
+        let mut symbol_names = SymbolNames::default();
+
+        for line in table.lines().filter(|l| !l.trim().is_empty()) {
+            let mut chars = line.chars();
+            let symbol = chars.next();
+            let separator = chars.next();
+            let name = chars.as_str().trim();
+
+            match (symbol, separator) {
+                (Some(symbol), Some(separator))
+                    if separator.is_whitespace() && !name.is_empty() =>
+                {
+                    symbol_names.0.insert(symbol, name.to_string());
+                }
+                _ => return Err(Error::InvalidSymbolTable(line.to_string())),
+            }
+        }
+
+        Ok(symbol_names)
+    }
+
+    /// Returns the name of a symbol, if it has one.
+    pub fn get(&self, symbol: char) -> Option<&str> {
+        self.0.get(&symbol).map(|name| name.as_str())
+    }
+}
+
+/// Spells out a password with the NATO/ICAO phonetic alphabet.
+///
+/// # Parameters
+///
+/// - `password`: Password to spell out.
+/// - `symbol_names`: Names for symbols.
+///
+/// # Returns
+///
+/// A comma-separated spelling such as `capital ALFA, seven, lowercase
+/// bravo, at-sign`.
+pub fn spell_phonetically(password: &str, symbol_names: &SymbolNames) -> String {
+    password
+        .chars()
+        .map(|c| {
+            if c.is_ascii_uppercase() {
+                format!(
+                    "capital {}",
+                    LETTER_NAMES[(c as u8 - b'A') as usize].to_uppercase()
+                )
+            } else if c.is_ascii_lowercase() {
+                format!("lowercase {}", LETTER_NAMES[(c as u8 - b'a') as usize])
+            } else if c.is_ascii_digit() {
+                DIGIT_NAMES[(c as u8 - b'0') as usize].to_string()
+            } else {
+                match symbol_names.get(c) {
+                    Some(name) => name.to_string(),
+                    None => format!("'{}'", c),
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spell_phonetically() {
+        assert_eq!(
+            spell_phonetically("A7b@", &SymbolNames::default()),
+            "capital ALFA, seven, lowercase bravo, at-sign"
+        );
+
+        assert_eq!(
+            spell_phonetically("Xz 0", &SymbolNames::default()),
+            "capital X-RAY, lowercase zulu, space, zero"
+        );
+
+        assert_eq!(spell_phonetically("é", &SymbolNames::default()), "'é'");
+    }
+
+    #[test]
+    fn test_symbol_names_from_table() {
+        let symbol_names = SymbolNames::from_table("@ klammeraffe\n\n#\tRaute\n").unwrap();
+
+        assert_eq!(symbol_names.get('@'), Some("klammeraffe"));
+        assert_eq!(symbol_names.get('#'), Some("Raute"));
+        assert_eq!(symbol_names.get('!'), Some("exclamation-mark"));
+
+        assert!(SymbolNames::from_table("@klammeraffe").is_err());
+        assert!(SymbolNames::from_table("@ ").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use bip39::Language;
+use rand::{rngs::OsRng, Rng};
+use randpass::Error;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Returns the built-in word list, the 2048 English BIP-39 words.
+pub(crate) fn default_word_list() -> Vec<String> {
+    Language::English
+        .word_list()
+        .iter()
+        .map(|w| w.to_string())
+        .collect()
+}
+
+/// Reads a word list with one word per line, ignoring blank lines and
+/// duplicates.
+///
+/// Lines may start with dice numbers, as in the EFF lists
+/// (`11111 abacus`); only the last field of a line is used.
+pub(crate) fn read_word_list(path: &Path) -> Result<Vec<String>, Error> {
+    let mut seen = HashSet::new();
+    let words: Vec<String> = fs::read_to_string(path)?
+        .lines()
+        .filter_map(|l| l.split_whitespace().last())
+        .filter(|w| seen.insert(w.to_string()))
+        .map(|w| w.to_string())
+        .collect();
+
+    if words.len() < 2 {
+        return Err(Error::InvalidWordList(path.display().to_string()));
+    }
+
+    Ok(words)
+}
+
+/// Joins `word_count` words drawn uniformly at random from `words`.
+pub(crate) fn create_passphrase(words: &[String], word_count: usize, separator: &str) -> String {
+    let mut rng = OsRng;
+
+    (0..word_count)
+        .map(|_| words[rng.gen_range(0..words.len())].as_str())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Calculates the entropy of a passphrase of `word_count` words drawn from
+/// a list of `word_list_size` distinct words.
+pub(crate) fn calculate_passphrase_entropy(word_list_size: usize, word_count: usize) -> f64 {
+    word_count as f64 * (word_list_size as f64).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_word_list() {
+        let words = default_word_list();
+
+        assert_eq!(words.len(), 2048);
+        assert_eq!(words[0], "abandon");
+        assert_eq!(calculate_passphrase_entropy(words.len(), 6), 66.0);
+    }
+
+    #[test]
+    fn test_read_word_list() {
+        let path = std::env::temp_dir().join(format!("randpass-words-{}", std::process::id()));
+
+        fs::write(&path, "11111\tabacus\n11112\tabdomen\n\nabacus\n").unwrap();
+        assert_eq!(read_word_list(&path).unwrap(), vec!["abacus", "abdomen"]);
+
+        fs::write(&path, "abacus\n").unwrap();
+        assert!(read_word_list(&path).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_create_passphrase() {
+        let words = vec!["a".to_string(), "b".to_string()];
+        let passphrase = create_passphrase(&words, 5, "-");
+
+        assert_eq!(passphrase.len(), 9);
+        assert!(passphrase.split('-').all(|w| w == "a" || w == "b"));
+    }
+}
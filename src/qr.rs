@@ -0,0 +1,200 @@
+use qrcode::render::{svg, unicode};
+use qrcode::{Color, QrCode};
+use randpass::Error;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::util::create_private_file;
+
+/// Size of one module in pixels in PNG output.
+const PNG_MODULE_SIZE: usize = 8;
+
+/// Width of the quiet zone around the code, in modules.
+const QUIET_ZONE: usize = 4;
+
+/// The minimum and maximum length of a WPA passphrase.
+pub(crate) const WIFI_PASSPHRASE_LENGTH: std::ops::RangeInclusive<usize> = 8..=63;
+
+fn encode(data: &str) -> Result<QrCode, Error> {
+    QrCode::new(data).map_err(|e| Error::QrEncodingFailed(e.to_string()))
+}
+
+/// Renders `data` as a QR code of Unicode half blocks, two modules per
+/// character cell.
+///
+/// The colors are inverted so that the code scans on terminals with a dark
+/// background.
+pub(crate) fn render_qr_terminal(data: &str) -> Result<String, Error> {
+    Ok(encode(data)?
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build())
+}
+
+/// Writes `data` as a QR code to an SVG or PNG file, depending on the
+/// file extension.
+pub(crate) fn write_qr_file(data: &str, path: &Path) -> Result<(), Error> {
+    let code = encode(data)?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("svg") => create_private_file(path)?.write_all(render_qr_svg(&code).as_bytes())?,
+        Some("png") => write_qr_png(&code, create_private_file(path)?)?,
+        _ => return Err(Error::UnsupportedQrFormat(path.display().to_string())),
+    }
+
+    Ok(())
+}
+
+fn render_qr_svg(code: &QrCode) -> String {
+    code.render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .dark_color(svg::Color("#000000"))
+        .light_color(svg::Color("#ffffff"))
+        .build()
+}
+
+fn write_qr_png<W: Write>(code: &QrCode, output: W) -> Result<(), Error> {
+    let colors = code.to_colors();
+    let modules = code.width();
+    let size = (modules + 2 * QUIET_ZONE) * PNG_MODULE_SIZE;
+    let mut pixels = vec![0xff; size * size];
+
+    for (i, color) in colors.iter().enumerate() {
+        if *color == Color::Light {
+            continue;
+        }
+
+        let x0 = (i % modules + QUIET_ZONE) * PNG_MODULE_SIZE;
+        let y0 = (i / modules + QUIET_ZONE) * PNG_MODULE_SIZE;
+
+        for y in y0..y0 + PNG_MODULE_SIZE {
+            pixels[y * size + x0..y * size + x0 + PNG_MODULE_SIZE].fill(0);
+        }
+    }
+
+    let mut encoder = png::Encoder::new(BufWriter::new(output), size as u32, size as u32);
+
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| Error::QrEncodingFailed(e.to_string()))
+}
+
+/// Encodes `data` as an SVG path with one unit square per dark module.
+///
+/// Returns the width of the code in modules, without a quiet zone, and the
+/// path data.
+pub(crate) fn qr_svg_path(data: &str) -> Result<(usize, String), Error> {
+    let code = encode(data)?;
+    let modules = code.width();
+    let path = code
+        .to_colors()
+        .iter()
+        .enumerate()
+        .filter(|(_, color)| **color == Color::Dark)
+        .map(|(i, _)| format!("M{} {}h1v1h-1z", i % modules, i / modules))
+        .collect();
+
+    Ok((modules, path))
+}
+
+/// Escapes the characters that are special in Wi-Fi QR payloads.
+fn escape_wifi(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(c, '\\' | ';' | ',' | '"' | ':') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Builds the `WIFI:` payload that lets phones join a WPA2/WPA3 network.
+pub(crate) fn wifi_payload(ssid: &str, passphrase: &str) -> Result<String, Error> {
+    if !WIFI_PASSPHRASE_LENGTH.contains(&passphrase.len())
+        || !passphrase.bytes().all(|c| (b' '..=b'~').contains(&c))
+    {
+        return Err(Error::InvalidWifiPassphrase);
+    }
+
+    Ok(format!(
+        "WIFI:T:WPA;S:{};P:{};;",
+        escape_wifi(ssid),
+        escape_wifi(passphrase)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_wifi_payload() {
+        assert_eq!(
+            wifi_payload("Home", "correct horse").unwrap(),
+            "WIFI:T:WPA;S:Home;P:correct horse;;"
+        );
+
+        assert_eq!(
+            wifi_payload("Café;\"Guest\"", "a:b,c;d\\e").unwrap(),
+            "WIFI:T:WPA;S:Café\\;\\\"Guest\\\";P:a\\:b\\,c\\;d\\\\e;;"
+        );
+
+        assert!(wifi_payload("Home", "short").is_err());
+        assert!(wifi_payload("Home", &"a".repeat(64)).is_err());
+        assert!(wifi_payload("Home", "pässword").is_err());
+    }
+
+    #[test]
+    fn test_render_qr_terminal() {
+        let rendered = render_qr_terminal("hunter2").unwrap();
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        // A version 1 code is 21 modules wide, plus a quiet zone of 4 on
+        // each side, at two modules per line.
+        assert_eq!(lines.len(), 15);
+        assert!(lines.iter().all(|l| l.chars().count() == 29));
+    }
+
+    #[test]
+    fn test_qr_svg_path() {
+        let (modules, path) = qr_svg_path("hunter2").unwrap();
+
+        assert_eq!(modules, 21);
+        // The top-left finder pattern starts with a dark module.
+        assert!(path.starts_with("M0 0h1v1h-1z"));
+    }
+
+    #[test]
+    fn test_write_qr_file() {
+        let dir = std::env::temp_dir().join(format!("randpass-qr-{}", std::process::id()));
+
+        fs::create_dir_all(&dir).unwrap();
+
+        write_qr_file("hunter2", &dir.join("code.svg")).unwrap();
+        assert!(fs::read_to_string(dir.join("code.svg"))
+            .unwrap()
+            .contains("<svg"));
+
+        write_qr_file("hunter2", &dir.join("code.png")).unwrap();
+        assert!(fs::read(dir.join("code.png"))
+            .unwrap()
+            .starts_with(b"\x89PNG\r\n\x1a\n"));
+
+        assert!(write_qr_file("hunter2", &dir.join("code.gif")).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
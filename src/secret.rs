@@ -0,0 +1,203 @@
+use crate::util::create_private_file;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use randpass::{Error, PasswordCriteria};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The key name used when no `--key` is given.
+pub(crate) const DEFAULT_KEY_NAME: &str = "password";
+
+/// A named entry of a secret, optionally with its own length and criteria.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SecretKey {
+    pub(crate) name: String,
+    pub(crate) password_length: Option<usize>,
+    pub(crate) criteria: Option<PasswordCriteria<'static>>,
+}
+
+impl FromStr for SecretKey {
+    type Err = Error;
+
+    /// Parses a key specification of the form `NAME[:LENGTH[:PRESET]]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+        let name = parts.next().unwrap_or_default();
+
+        if !is_valid_key_name(name) {
+            return Err(Error::InvalidKeySpec(s.to_string()));
+        }
+
+        let password_length = match parts.next() {
+            Some("") | None => None,
+            Some(l) => Some(
+                l.parse::<usize>()
+                    .map_err(|_| Error::InvalidKeySpec(s.to_string()))?,
+            ),
+        };
+        let criteria = match parts.next() {
+            Some("") | None => None,
+            Some(p) => Some(p.parse::<PasswordCriteria>()?),
+        };
+
+        Ok(SecretKey {
+            name: name.to_string(),
+            password_length,
+            criteria,
+        })
+    }
+}
+
+/// Checks a key name against the rules shared by Kubernetes `data` keys
+/// and Docker secret file names.
+fn is_valid_key_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && name
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_' | b'.'))
+}
+
+/// Checks a resource name against RFC 1123 subdomain rules.
+fn is_valid_resource_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 253
+        && name
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, b'-' | b'.'))
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+}
+
+/// Renders a `v1/Secret` manifest with base64-encoded `data` entries.
+pub(crate) fn render_kubernetes_secret(
+    name: &str,
+    namespace: Option<&str>,
+    entries: &[(String, String)],
+) -> Result<String, Error> {
+    if !is_valid_resource_name(name) {
+        return Err(Error::InvalidResourceName(name.to_string()));
+    }
+
+    let mut manifest = String::from("apiVersion: v1\nkind: Secret\nmetadata:\n");
+
+    manifest.push_str(&format!("  name: {}\n", name));
+
+    if let Some(namespace) = namespace {
+        if !is_valid_resource_name(namespace) {
+            return Err(Error::InvalidResourceName(namespace.to_string()));
+        }
+        manifest.push_str(&format!("  namespace: {}\n", namespace));
+    }
+
+    manifest.push_str("type: Opaque\ndata:\n");
+
+    for (key, password) in entries {
+        manifest.push_str(&format!("  {}: {}\n", key, STANDARD.encode(password)));
+    }
+
+    Ok(manifest)
+}
+
+/// Writes each entry into its own file inside `dir`, as expected by Docker
+/// and Compose file-based secrets.
+pub(crate) fn write_docker_secrets(dir: &Path, entries: &[(String, String)]) -> Result<(), Error> {
+    fs::create_dir_all(dir)?;
+
+    for (key, password) in entries {
+        create_private_file(&dir.join(key))?.write_all(password.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_secret_key() {
+        assert_eq!(
+            "db-password".parse::<SecretKey>().unwrap(),
+            SecretKey {
+                name: "db-password".to_string(),
+                password_length: None,
+                criteria: None,
+            }
+        );
+
+        assert_eq!(
+            "api_token:40:symbols".parse::<SecretKey>().unwrap(),
+            SecretKey {
+                name: "api_token".to_string(),
+                password_length: Some(40),
+                criteria: Some(PasswordCriteria::AllPrintableChars),
+            }
+        );
+
+        assert_eq!(
+            "pin::digits".parse::<SecretKey>().unwrap(),
+            SecretKey {
+                name: "pin".to_string(),
+                password_length: None,
+                criteria: Some(PasswordCriteria::DigitsOnly),
+            }
+        );
+
+        assert!("".parse::<SecretKey>().is_err());
+        assert!("a/b".parse::<SecretKey>().is_err());
+        assert!("key:abc".parse::<SecretKey>().is_err());
+        assert!("key:10:unknown".parse::<SecretKey>().is_err());
+    }
+
+    #[test]
+    fn test_render_kubernetes_secret() {
+        let entries = vec![
+            ("username".to_string(), "admin".to_string()),
+            ("password".to_string(), "hunter2".to_string()),
+        ];
+
+        assert_eq!(
+            render_kubernetes_secret("db-credentials", Some("prod"), &entries).unwrap(),
+            "apiVersion: v1\n\
+             kind: Secret\n\
+             metadata:\n  \
+               name: db-credentials\n  \
+               namespace: prod\n\
+             type: Opaque\n\
+             data:\n  \
+               username: YWRtaW4=\n  \
+               password: aHVudGVyMg==\n"
+        );
+
+        assert!(render_kubernetes_secret("DB", None, &entries).is_err());
+        assert!(render_kubernetes_secret("db", Some("-prod"), &entries).is_err());
+    }
+
+    #[test]
+    fn test_write_docker_secrets() {
+        let dir = std::env::temp_dir().join(format!("randpass-secrets-{}", std::process::id()));
+        let entries = vec![("db_password".to_string(), "hunter2".to_string())];
+
+        write_docker_secrets(&dir, &entries).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.join("db_password")).unwrap(),
+            "hunter2"
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.join("db_password"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+use randpass::{spell_phonetically, Error, SymbolNames};
+use std::io::Write;
+use std::path::Path;
+use textwrap::wrap;
+
+use crate::export::escape_xml;
+use crate::qr::qr_svg_path;
+use crate::util::create_private_file;
+
+/// Fonts that tell look-alike characters such as `l`, `1`, and `I` apart.
+const PASSWORD_FONTS: &str =
+    "'B612 Mono', 'Atkinson Hyperlegible Mono', 'DejaVu Sans Mono', monospace";
+
+/// Width of an SVG sheet in user units.
+const SVG_WIDTH: usize = 800;
+
+/// Side length of a QR code on a card, including its quiet zone.
+const QR_SIZE: usize = 120;
+
+/// Maximum line length of the phonetic spelling on an SVG card.
+const PHONETIC_WRAP_WIDTH: usize = 80;
+
+/// A password, optionally for a named account, to be printed on a card.
+pub(crate) struct Card {
+    pub(crate) username: Option<String>,
+    pub(crate) password: String,
+}
+
+/// Renders the cards as an HTML or SVG page, depending on the file
+/// extension, and writes it to `path`.
+pub(crate) fn write_sheet(
+    path: &Path,
+    cards: &[Card],
+    symbol_names: &SymbolNames,
+    include_qr: bool,
+) -> Result<(), Error> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    let sheet = match extension.as_deref() {
+        Some("html") | Some("htm") => render_html_sheet(cards, symbol_names, include_qr)?,
+        Some("svg") => render_svg_sheet(cards, symbol_names, include_qr)?,
+        _ => return Err(Error::UnsupportedSheetFormat(path.display().to_string())),
+    };
+
+    create_private_file(path)?.write_all(sheet.as_bytes())?;
+
+    Ok(())
+}
+
+/// Renders a QR code as an `<svg>` element of the given size, with a quiet
+/// zone of four modules.
+fn render_qr_element(data: &str, x: usize, y: usize, size: usize) -> Result<String, Error> {
+    let (modules, path) = qr_svg_path(data)?;
+
+    Ok(format!(
+        "<svg x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" viewBox=\"-4 -4 {} {}\">\
+         <rect x=\"-4\" y=\"-4\" width=\"100%\" height=\"100%\" fill=\"#fff\"/>\
+         <path d=\"{}\" fill=\"#000\"/></svg>",
+        x,
+        y,
+        size,
+        size,
+        modules + 8,
+        modules + 8,
+        path
+    ))
+}
+
+pub(crate) fn render_html_sheet(
+    cards: &[Card],
+    symbol_names: &SymbolNames,
+    include_qr: bool,
+) -> Result<String, Error> {
+    let mut html = format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>Credentials</title>\n\
+         <style>\n\
+         body {{ font-family: sans-serif; margin: 2em; }}\n\
+         .card {{ border: 1px solid #000; border-radius: 8px; padding: 1em; margin-bottom: 1em; \
+         display: flex; gap: 1em; page-break-inside: avoid; break-inside: avoid; }}\n\
+         .card > div {{ flex: 1; }}\n\
+         .username {{ font-weight: bold; }}\n\
+         .password {{ font-family: {}; font-size: 1.6em; letter-spacing: 0.1em; margin: 0.3em 0; }}\n\
+         .phonetic {{ font-size: 0.8em; color: #444; }}\n\
+         .qr {{ width: {}px; height: {}px; }}\n\
+         </style>\n\
+         </head>\n\
+         <body>\n",
+        PASSWORD_FONTS, QR_SIZE, QR_SIZE
+    );
+
+    for card in cards {
+        html.push_str("<section class=\"card\">\n<div>\n");
+
+        if let Some(ref username) = card.username {
+            html.push_str(&format!(
+                "<div class=\"username\">{}</div>\n",
+                escape_xml(username)
+            ));
+        }
+
+        html.push_str(&format!(
+            "<div class=\"password\">{}</div>\n<div class=\"phonetic\">{}</div>\n</div>\n",
+            escape_xml(&card.password),
+            escape_xml(&spell_phonetically(&card.password, symbol_names))
+        ));
+
+        if include_qr {
+            html.push_str(&format!(
+                "<svg class=\"qr\" viewBox=\"0 0 {} {}\">{}</svg>\n",
+                QR_SIZE,
+                QR_SIZE,
+                render_qr_element(&card.password, 0, 0, QR_SIZE)?
+            ));
+        }
+
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+
+    Ok(html)
+}
+
+pub(crate) fn render_svg_sheet(
+    cards: &[Card],
+    symbol_names: &SymbolNames,
+    include_qr: bool,
+) -> Result<String, Error> {
+    let mut body = String::new();
+    let mut y = 20;
+
+    for card in cards {
+        let phonetic = spell_phonetically(&card.password, symbol_names);
+        let phonetic_lines = wrap(&phonetic, PHONETIC_WRAP_WIDTH);
+        let text_height = 80 + 14 * phonetic_lines.len();
+        let height = if include_qr {
+            text_height.max(QR_SIZE + 20)
+        } else {
+            text_height
+        };
+
+        body.push_str(&format!(
+            "<rect x=\"20\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"8\" fill=\"none\" stroke=\"#000\"/>\n",
+            y,
+            SVG_WIDTH - 40,
+            height
+        ));
+
+        if let Some(ref username) = card.username {
+            body.push_str(&format!(
+                "<text x=\"36\" y=\"{}\" font-family=\"sans-serif\" font-size=\"14\" font-weight=\"bold\">{}</text>\n",
+                y + 26,
+                escape_xml(username)
+            ));
+        }
+
+        body.push_str(&format!(
+            "<text x=\"36\" y=\"{}\" font-family=\"{}\" font-size=\"24\" xml:space=\"preserve\">{}</text>\n",
+            y + 58,
+            PASSWORD_FONTS,
+            escape_xml(&card.password)
+        ));
+
+        for (i, line) in phonetic_lines.iter().enumerate() {
+            body.push_str(&format!(
+                "<text x=\"36\" y=\"{}\" font-family=\"sans-serif\" font-size=\"11\" fill=\"#444\">{}</text>\n",
+                y + 80 + 14 * i,
+                escape_xml(line)
+            ));
+        }
+
+        if include_qr {
+            body.push_str(&render_qr_element(
+                &card.password,
+                SVG_WIDTH - 30 - QR_SIZE,
+                y + 10,
+                QR_SIZE,
+            )?);
+            body.push('\n');
+        }
+
+        y += height + 20;
+    }
+
+    Ok(format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"#fff\"/>\n\
+         {}</svg>\n",
+        SVG_WIDTH, y, SVG_WIDTH, y, body
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards() -> Vec<Card> {
+        vec![
+            Card {
+                username: Some("alice & bob".to_string()),
+                password: "a<B1".to_string(),
+            },
+            Card {
+                username: None,
+                password: "x9".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_html_sheet() {
+        let html = render_html_sheet(&cards(), &SymbolNames::default(), true).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert_eq!(html.matches("<section class=\"card\">").count(), 2);
+        assert!(html.contains("alice &amp; bob"));
+        assert!(html.contains("<div class=\"password\">a&lt;B1</div>"));
+        assert!(html.contains("lowercase alfa, less-than-sign, capital BRAVO, one"));
+        assert_eq!(html.matches("<path").count(), 2);
+
+        let html = render_html_sheet(&cards(), &SymbolNames::default(), false).unwrap();
+
+        assert!(!html.contains("<path"));
+    }
+
+    #[test]
+    fn test_render_svg_sheet() {
+        let svg = render_svg_sheet(&cards(), &SymbolNames::default(), false).unwrap();
+
+        assert!(svg.contains("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains(">a&lt;B1</text>"));
+        assert!(svg.contains(">lowercase x-ray, nine</text>"));
+        assert_eq!(svg.matches("rx=\"8\"").count(), 2);
+    }
+
+    #[test]
+    fn test_write_sheet_with_unsupported_format() {
+        assert!(write_sheet(
+            Path::new("sheet.pdf"),
+            &cards(),
+            &SymbolNames::default(),
+            false
+        )
+        .is_err());
+    }
+}
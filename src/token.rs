@@ -0,0 +1,55 @@
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use clap::ValueEnum;
+use rand::{rngs::OsRng, RngCore};
+
+/// Text encodings for random tokens.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum TokenEncoding {
+    /// Lowercase hexadecimal
+    Hex,
+
+    /// Standard base64 with padding
+    Base64,
+
+    /// URL-safe base64 without padding
+    Base64url,
+}
+
+/// Encodes raw token bytes as text.
+pub(crate) fn encode_token(bytes: &[u8], encoding: TokenEncoding) -> String {
+    match encoding {
+        TokenEncoding::Hex => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+        TokenEncoding::Base64 => STANDARD.encode(bytes),
+        TokenEncoding::Base64url => URL_SAFE_NO_PAD.encode(bytes),
+    }
+}
+
+/// Creates a token of `byte_count` random bytes in the given encoding.
+pub(crate) fn create_token(byte_count: usize, encoding: TokenEncoding) -> String {
+    let mut bytes = vec![0; byte_count];
+
+    OsRng.fill_bytes(&mut bytes);
+
+    encode_token(&bytes, encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_token() {
+        let bytes = [0xfb, 0xff, 0x00, 0x10];
+
+        assert_eq!(encode_token(&bytes, TokenEncoding::Hex), "fbff0010");
+        assert_eq!(encode_token(&bytes, TokenEncoding::Base64), "+/8AEA==");
+        assert_eq!(encode_token(&bytes, TokenEncoding::Base64url), "-_8AEA");
+    }
+
+    #[test]
+    fn test_create_token() {
+        assert_eq!(create_token(16, TokenEncoding::Hex).len(), 32);
+        assert_eq!(create_token(32, TokenEncoding::Base64url).len(), 43);
+    }
+}
@@ -1,8 +1,11 @@
 use crossterm::terminal::size;
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::{LN_2, PI};
+use std::fs::{File, OpenOptions};
 use std::io;
 use std::io::IsTerminal;
+use std::path::Path;
 use std::{borrow::Cow, collections::HashSet};
 use textwrap::wrap;
 
@@ -149,9 +152,37 @@ pub(crate) fn parse_escape_sequences(input: &str) -> String {
     result
 }
 
+/// The largest `n` whose factorial is summed term by term.
+const EXACT_FACTORIAL_LIMIT: u64 = 32;
+
+/// Returns `log2(n!)`, summed exactly for small `n` and from Stirling's
+/// series for `ln(Gamma(n + 1))` otherwise, whose error is below
+/// `1 / (1680 n^7)`.
 #[allow(dead_code)]
 pub(crate) fn log2_factorial(n: u64) -> f64 {
-    (1..=n).map(|x| x as f64).map(|x| x.log(2.0)).sum()
+    if n <= EXACT_FACTORIAL_LIMIT {
+        return (1..=n).map(|x| x as f64).map(|x| x.log(2.0)).sum();
+    }
+
+    let x = n as f64;
+    let ln_gamma = x * x.ln() - x + 0.5 * (2.0 * PI * x).ln() + 1.0 / (12.0 * x)
+        - 1.0 / (360.0 * x.powi(3))
+        + 1.0 / (1260.0 * x.powi(5));
+
+    ln_gamma / LN_2
+}
+
+/// Returns `log2(sum(2^b))` for the given `bits`, without overflowing for
+/// large ones, or negative infinity if there are none.
+#[allow(dead_code)]
+pub(crate) fn log2_sum_exp2(bits: &[f64]) -> f64 {
+    let most = bits.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    if most == f64::NEG_INFINITY {
+        return most;
+    }
+
+    most + bits.iter().map(|b| (b - most).exp2()).sum::<f64>().log2()
 }
 
 #[allow(dead_code)]
@@ -254,7 +285,98 @@ pub(crate) fn calculate_char_multiplicities(charset: &[u8]) -> Vec<usize> {
     multiplicities
 }
 
+/// Returns the distinct characters of `charset` in order, with their
+/// multiplicities.
+#[allow(dead_code)]
+pub(crate) fn calculate_char_multiplicity_pairs(charset: &[u8]) -> Vec<(u8, usize)> {
+    let mut multiplicities: BTreeMap<u8, usize> = BTreeMap::new();
+
+    for &c in charset {
+        *multiplicities.entry(c).or_insert(0) += 1;
+    }
+
+    multiplicities.into_iter().collect()
+}
+
+#[allow(dead_code)]
+pub(crate) fn colorize_password(password: &str) -> String {
+    let mut colorized = String::new();
+
+    for c in password.chars() {
+        let color = if c.is_ascii_uppercase() {
+            CYAN
+        } else if c.is_ascii_lowercase() {
+            ""
+        } else if c.is_ascii_digit() {
+            YELLOW
+        } else {
+            GREEN
+        };
+
+        if color.is_empty() {
+            colorized.push(c);
+        } else {
+            colorized.push_str(&format!("{}{}{}", color, c, RESET));
+        }
+    }
+
+    colorized
+}
+
+#[allow(dead_code)]
+pub(crate) fn create_ruler(length: usize, offset: usize, color: bool) -> String {
+    let mut ruler = " ".repeat(offset);
+
+    for position in 1..=length {
+        let digit = char::from(b'0' + (position % 10) as u8);
+
+        if color && position % 5 == 0 {
+            ruler.push_str(&format!("{}{}{}", BOLD, digit, RESET));
+        } else {
+            ruler.push(digit);
+        }
+    }
+
+    ruler
+}
+
+/// Creates or truncates a file that only its owner can read and write.
+///
+/// The mode given to `open` only applies to a new file, so an existing
+/// regular file has its permissions restricted as well. Other files, such
+/// as `/dev/stdout`, are left alone.
+#[allow(dead_code)]
+pub(crate) fn create_private_file(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let file = options.open(path)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if file.metadata()?.is_file() {
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+
+    Ok(file)
+}
+
 #[cfg(test)]
+#[allow(
+    clippy::needless_borrow,
+    clippy::unnecessary_cast,
+    clippy::unnecessary_to_owned
+)]
 mod tests {
     use super::*;
 
@@ -431,6 +553,14 @@ mod tests {
         assert_approx_eq!(log2_factorial(5), (120.0 as f64).log(2.0));
 
         assert_approx_eq!(log2_factorial(10), (3628800.0 as f64).log(2.0));
+
+        for n in [33, 34, 100, 1000, 100_000] {
+            let exact: f64 = (1..=n).map(|x| (x as f64).log2()).sum();
+
+            assert!((log2_factorial(n) - exact).abs() / exact < 1.0e-12);
+        }
+
+        assert!(log2_factorial(u64::MAX).is_finite());
     }
 
     #[test]
@@ -456,6 +586,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_colorize_password() {
+        assert_eq!(
+            colorize_password("aB1!"),
+            format!("a{}B{}{}1{}{}!{}", CYAN, RESET, YELLOW, RESET, GREEN, RESET)
+        );
+    }
+
+    #[test]
+    fn test_create_ruler() {
+        assert_eq!(create_ruler(12, 0, false), "123456789012");
+
+        assert_eq!(create_ruler(3, 2, false), "  123");
+
+        assert_eq!(create_ruler(5, 0, true), format!("1234{}5{}", BOLD, RESET));
+    }
+
     #[test]
     fn test_calculate_char_multiplicities() {
         assert_eq!(
@@ -463,4 +610,31 @@ mod tests {
             vec![1, 1, 1, 2]
         );
     }
+
+    #[test]
+    fn test_create_private_file() {
+        let path = std::env::temp_dir().join(format!("randpass-private-{}", std::process::id()));
+
+        std::fs::write(&path, "old contents").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        }
+
+        create_private_file(&path).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }